@@ -0,0 +1,236 @@
+use core_affinity::CoreId;
+use sysinfo::{CpuRefreshKind, RefreshKind, System};
+
+/// CPU ids in a list must be below this, so a typo cannot expand into a huge range.
+/// Matches the largest CPU count the Linux kernel supports.
+const MAX_CPU_ID: usize = 8192;
+
+/// Where stress workers are allowed to run.
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub enum CpuAffinity {
+    /// Let the OS scheduler place and move workers freely.
+    #[default]
+    Unpinned,
+    /// Pin worker `i` to the `i`-th lowest listed logical CPU, wrapping around the list.
+    Cpus(Vec<usize>),
+    /// Pin workers to the first logical CPU of each physical core, so no two workers
+    /// share a core through SMT.
+    OnePerPhysicalCore,
+}
+
+impl CpuAffinity {
+    /// Parses a CPU list such as "0,2,4-7", "physical" for one worker per physical
+    /// core, or an empty string for no pinning.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let spec = spec.trim();
+        if spec.is_empty() {
+            return Ok(CpuAffinity::Unpinned);
+        }
+        if matches!(spec, "p" | "phys" | "physical") {
+            return Ok(CpuAffinity::OnePerPhysicalCore);
+        }
+
+        // Listed CPUs, indexed by id so repeated ranges take no extra memory
+        let mut listed = vec![false; MAX_CPU_ID];
+        for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let parse = |s: &str| match s.trim().parse::<usize>() {
+                Ok(cpu) if cpu < MAX_CPU_ID => Ok(cpu),
+                Ok(_) => Err(format!(
+                    "CPU id '{}' is too large, the limit is {}",
+                    s.trim(),
+                    MAX_CPU_ID - 1
+                )),
+                Err(_) => Err(format!("Invalid CPU id '{}'", s.trim())),
+            };
+            match part.split_once('-') {
+                Some((first, last)) => {
+                    let (first, last) = (parse(first)?, parse(last)?);
+                    if first > last {
+                        return Err(format!("Invalid CPU range '{}'", part));
+                    }
+                    listed[first..=last].fill(true);
+                }
+                None => listed[parse(part)?] = true,
+            }
+        }
+        let cpus: Vec<usize> = (0..MAX_CPU_ID).filter(|&cpu| listed[cpu]).collect();
+        if cpus.is_empty() {
+            return Err(format!("No CPUs in '{}'", spec));
+        }
+        Ok(CpuAffinity::Cpus(cpus))
+    }
+
+    /// Logical CPUs workers are pinned to, or `None` when unpinned. Fails if a listed
+    /// CPU does not exist.
+    pub fn resolve(&self, logical_cpus: usize) -> Result<Option<Vec<usize>>, String> {
+        match self {
+            CpuAffinity::Unpinned => Ok(None),
+            CpuAffinity::Cpus(cpus) => match cpus.iter().find(|&&cpu| cpu >= logical_cpus) {
+                Some(cpu) => Err(format!(
+                    "CPU {} does not exist, this machine has {} logical CPUs",
+                    cpu, logical_cpus
+                )),
+                None => Ok(Some(cpus.clone())),
+            },
+            CpuAffinity::OnePerPhysicalCore => Ok(Some(physical_core_cpus(logical_cpus))),
+        }
+    }
+}
+
+/// The lowest-numbered logical CPU of every physical core.
+pub fn physical_core_cpus(logical_cpus: usize) -> Vec<usize> {
+    #[cfg(target_os = "linux")]
+    {
+        let mut cpus = Vec::new();
+        for cpu in 0..logical_cpus {
+            let path = format!(
+                "/sys/devices/system/cpu/cpu{}/topology/thread_siblings_list",
+                cpu
+            );
+            let first_sibling = std::fs::read_to_string(path).ok().and_then(|list| {
+                match CpuAffinity::parse(&list) {
+                    Ok(CpuAffinity::Cpus(siblings)) => siblings.into_iter().min(),
+                    _ => None,
+                }
+            });
+            match first_sibling {
+                Some(first) if first == cpu => cpus.push(cpu),
+                Some(_) => {}
+                None => return physical_core_cpus_fallback(logical_cpus),
+            }
+        }
+        cpus
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        physical_core_cpus_fallback(logical_cpus)
+    }
+}
+
+/// Assumes SMT siblings are numbered next to each other, as Windows does.
+fn physical_core_cpus_fallback(logical_cpus: usize) -> Vec<usize> {
+    let physical = System::physical_core_count()
+        .unwrap_or(logical_cpus)
+        .clamp(1, logical_cpus.max(1));
+    let step = (logical_cpus / physical).max(1);
+    (0..logical_cpus).step_by(step).take(physical).collect()
+}
+
+/// Number of logical CPUs the OS reports, regardless of this process' own affinity.
+pub fn logical_cpu_count() -> usize {
+    let system =
+        System::new_with_specifics(RefreshKind::nothing().with_cpu(CpuRefreshKind::nothing()));
+    system.cpus().len().max(1)
+}
+
+/// Pins the calling thread to one logical CPU. Returns false if the OS refused.
+pub fn pin_current_thread(cpu: usize) -> bool {
+    core_affinity::set_for_current(CoreId { id: cpu })
+}
+
+/// Lets the calling thread run on every CPU the process may use again. Returns false
+/// where the platform offers no way to undo `pin_current_thread`.
+pub fn unpin_current_thread() -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        // The main thread's mask is the process-wide one unless someone pinned it.
+        unsafe {
+            let mut set: libc::cpu_set_t = std::mem::zeroed();
+            let size = size_of::<libc::cpu_set_t>();
+            libc::sched_getaffinity(libc::getpid(), size, &mut set) == 0
+                && libc::sched_setaffinity(0, size, &set) == 0
+        }
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        false
+    }
+}
+
+/// Logical CPU the calling thread is running on, where the platform can tell.
+pub fn current_cpu() -> Option<usize> {
+    #[cfg(target_os = "linux")]
+    {
+        let cpu = unsafe { libc::sched_getcpu() };
+        if cpu >= 0 {
+            return Some(cpu as usize);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_empty_means_unpinned() {
+        assert_eq!(CpuAffinity::parse(""), Ok(CpuAffinity::Unpinned));
+        assert_eq!(CpuAffinity::parse("  "), Ok(CpuAffinity::Unpinned));
+    }
+
+    #[test]
+    fn parse_physical_and_its_abbreviations() {
+        for spec in ["p", "phys", "physical", " physical "] {
+            assert_eq!(
+                CpuAffinity::parse(spec),
+                Ok(CpuAffinity::OnePerPhysicalCore),
+                "{:?}",
+                spec
+            );
+        }
+    }
+
+    #[test]
+    fn parse_lists_and_ranges() {
+        assert_eq!(
+            CpuAffinity::parse("0,2,4-7"),
+            Ok(CpuAffinity::Cpus(vec![0, 2, 4, 5, 6, 7]))
+        );
+        assert_eq!(CpuAffinity::parse("3"), Ok(CpuAffinity::Cpus(vec![3])));
+        assert_eq!(CpuAffinity::parse("5-5"), Ok(CpuAffinity::Cpus(vec![5])));
+        assert_eq!(
+            CpuAffinity::parse(" 0 - 2 , 5 ,"),
+            Ok(CpuAffinity::Cpus(vec![0, 1, 2, 5]))
+        );
+    }
+
+    #[test]
+    fn parse_sorts_and_removes_duplicates() {
+        assert_eq!(
+            CpuAffinity::parse("6,3,1-4,3"),
+            Ok(CpuAffinity::Cpus(vec![1, 2, 3, 4, 6]))
+        );
+    }
+
+    #[test]
+    fn parse_rejects_garbage() {
+        for spec in [
+            "a",
+            "1,b",
+            "3-1",
+            "1-",
+            "-1",
+            "1-2-3",
+            ",",
+            " , ",
+            "1.5",
+            "physicals",
+            "8192",
+            "0-99999999999",
+            "0-99999999999999999999999",
+        ] {
+            assert!(CpuAffinity::parse(spec).is_err(), "{:?}", spec);
+        }
+    }
+
+    #[test]
+    fn resolve_checks_the_cpus_exist() {
+        assert_eq!(CpuAffinity::Unpinned.resolve(4), Ok(None));
+        assert_eq!(
+            CpuAffinity::Cpus(vec![1, 3]).resolve(4),
+            Ok(Some(vec![1, 3]))
+        );
+        assert!(CpuAffinity::Cpus(vec![1, 4]).resolve(4).is_err());
+    }
+}
@@ -0,0 +1,71 @@
+use std::time::Duration;
+
+use crate::{
+    affinity::CpuAffinity, pool::WorkerPriority, profile::LoadProfile, thermal::ThermalLimit,
+};
+
+/// Everything `CpuExplosion::stress_test_cpu` needs to know about a run.
+#[derive(Clone, Debug)]
+pub struct StressConfig {
+    pub duration_sec: u64,
+    /// Number of worker threads.
+    pub threads: usize,
+    /// Name of a workload in the stress tester's registry.
+    pub workload: String,
+    pub affinity: CpuAffinity,
+    pub priority: WorkerPriority,
+    /// How often throughput samples are published to subscribers.
+    pub sample_interval: Duration,
+    /// CPU usage in percent to hold the workers' CPUs at, or `None` to run flat out.
+    pub target_load: Option<f32>,
+    /// Timeline of target loads and worker counts. Takes precedence over `target_load`.
+    pub profile: Option<LoadProfile>,
+    /// Stops the run early when sensors get too hot. Checked once per sample.
+    pub thermal_limit: Option<ThermalLimit>,
+}
+
+impl StressConfig {
+    pub fn new(duration_sec: u64, threads: usize, workload: impl Into<String>) -> Self {
+        StressConfig {
+            duration_sec,
+            threads,
+            workload: workload.into(),
+            affinity: CpuAffinity::Unpinned,
+            priority: WorkerPriority::Normal,
+            sample_interval: Duration::from_secs(1),
+            target_load: None,
+            profile: None,
+            thermal_limit: None,
+        }
+    }
+
+    pub fn with_affinity(mut self, affinity: CpuAffinity) -> Self {
+        self.affinity = affinity;
+        self
+    }
+
+    pub fn with_priority(mut self, priority: WorkerPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Throttles the workers so their CPUs stay at `percent` usage.
+    pub fn with_target_load(mut self, percent: f32) -> Self {
+        self.target_load = Some(percent);
+        self
+    }
+
+    pub fn with_thermal_limit(mut self, limit: ThermalLimit) -> Self {
+        self.thermal_limit = Some(limit);
+        self
+    }
+
+    /// Follows `profile`, running as long as it lasts and with enough workers for its
+    /// largest segment.
+    pub fn with_profile(mut self, profile: LoadProfile) -> Self {
+        self.duration_sec = profile.duration().as_secs_f64().ceil() as u64;
+        self.threads = self.threads.max(profile.max_threads().unwrap_or(0));
+        self.profile = Some(profile);
+        self
+    }
+}
@@ -0,0 +1,265 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicU32, AtomicUsize, Ordering},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    CpuUsage, SystemUsage,
+    profile::{LoadProfile, ProfilePoint},
+    worker::RunState,
+};
+
+/// Length of one busy/sleep cycle. Short enough that sampled CPU usage looks smooth,
+/// long enough to cover several batches of every workload.
+pub(crate) const PERIOD: Duration = Duration::from_millis(100);
+/// How often the controller reads CPU usage and adjusts the duty cycle.
+const CONTROL_INTERVAL: Duration = Duration::from_millis(500);
+/// Share of the load error corrected per control step.
+const GAIN: f32 = 0.5;
+/// Lowest duty cycle, so workers still make progress and the controller sees them.
+const MIN_DUTY: f32 = 0.01;
+/// Target changes above this many percent points per step are left to settle before
+/// correcting, as the last reading still shows the old load.
+const SETTLE_THRESHOLD: f32 = 5.0;
+
+/// How hard the workers of a run may work right now.
+pub(crate) struct LoadControl {
+    /// Share of each period workers may be busy, from `MIN_DUTY` to 1.0, as f32 bits.
+    duty: AtomicU32,
+    /// Target CPU usage in percent as f32 bits, NaN when running flat out.
+    target: AtomicU32,
+    /// Workers with a lower thread index run, the others idle.
+    active_threads: AtomicUsize,
+}
+
+impl LoadControl {
+    /// Flat out on every worker.
+    pub fn full(threads: usize) -> Self {
+        LoadControl {
+            duty: AtomicU32::new(1.0f32.to_bits()),
+            target: AtomicU32::new(f32::NAN.to_bits()),
+            active_threads: AtomicUsize::new(threads),
+        }
+    }
+
+    /// Starts at the duty cycle matching `target` percent, corrected from there.
+    pub fn with_target(target: f32, active_threads: usize) -> Self {
+        let load = Self::full(active_threads);
+        load.set_target(target);
+        load.set_duty(target / 100.0);
+        load
+    }
+
+    pub fn duty(&self) -> f32 {
+        f32::from_bits(self.duty.load(Ordering::Relaxed))
+    }
+
+    fn set_duty(&self, duty: f32) {
+        self.duty
+            .store(duty.clamp(MIN_DUTY, 1.0).to_bits(), Ordering::Relaxed);
+    }
+
+    /// Target CPU usage in percent, or `None` when running flat out.
+    pub fn target(&self) -> Option<f32> {
+        Some(f32::from_bits(self.target.load(Ordering::Relaxed))).filter(|t| !t.is_nan())
+    }
+
+    fn set_target(&self, target: f32) {
+        self.target.store(target.to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn active_threads(&self) -> usize {
+        self.active_threads.load(Ordering::Relaxed)
+    }
+
+    pub fn is_active(&self, thread_index: usize) -> bool {
+        thread_index < self.active_threads()
+    }
+
+    /// One control step towards `point`, given the `measured` mean usage of the
+    /// workers' CPUs. A new target moves the duty cycle by the same amount straight
+    /// away; the measured error is only corrected while the target and the number of
+    /// workers hold steady.
+    fn step(&self, point: ProfilePoint, measured: f32, threads: usize) {
+        let previous_target = self.target().unwrap_or(100.0);
+        let previous_active = self.active_threads();
+
+        // A target of zero idles every worker
+        let active = if point.load > 0.0 {
+            point.threads.unwrap_or(threads).min(threads)
+        } else {
+            0
+        };
+        let mut duty = self.duty() + (point.load - previous_target) / 100.0;
+        if active == previous_active
+            && active > 0
+            && (point.load - previous_target).abs() < SETTLE_THRESHOLD
+        {
+            duty += GAIN * (point.load - measured.min(100.0)) / 100.0;
+        }
+        self.set_target(point.load);
+        self.set_duty(duty);
+        self.active_threads.store(active, Ordering::Relaxed);
+    }
+}
+
+/// Puts a worker to sleep once it has used up its busy share of the current period.
+pub(crate) struct Throttle {
+    period_start: Instant,
+}
+
+impl Throttle {
+    pub fn new() -> Self {
+        Throttle {
+            period_start: Instant::now(),
+        }
+    }
+
+    /// Called after every batch.
+    pub fn after_batch(&mut self, duty: f32) {
+        let busy = self.period_start.elapsed();
+        if duty >= 1.0 {
+            if busy >= PERIOD {
+                self.period_start = Instant::now();
+            }
+            return;
+        }
+        if busy.as_secs_f32() >= duty * PERIOD.as_secs_f32() {
+            thread::sleep(PERIOD.saturating_sub(busy));
+            self.period_start = Instant::now();
+        }
+    }
+}
+
+/// Plays back `profile` until aborted: sets the number of active workers and adjusts
+/// the duty cycle so their CPUs follow the target load.
+pub(crate) async fn follow_profile(run: Arc<RunState>, profile: LoadProfile, threads: usize) {
+    let mut system_usage = SystemUsage::new();
+    let mut ticker = tokio::time::interval(CONTROL_INTERVAL);
+    ticker.tick().await;
+
+    loop {
+        ticker.tick().await;
+        // Read even while paused so the next reading only covers running time
+        let (_, usage) = system_usage.get_cpu_info();
+        if run.pause.is_paused() {
+            continue;
+        }
+        let Some(point) = profile.at(run.elapsed()) else {
+            continue;
+        };
+        let measured = mean_usage(&run.worker_cpus(&usage));
+        run.load.step(point, measured, threads);
+    }
+}
+
+/// Mean usage in percent, 0 for no CPUs.
+pub(crate) fn mean_usage(cpus: &[&CpuUsage]) -> f32 {
+    if cpus.is_empty() {
+        return 0.0;
+    }
+    cpus.iter().map(|cpu| cpu.usage).sum::<f32>() / cpus.len() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(load: f32, threads: Option<usize>) -> ProfilePoint {
+        ProfilePoint { load, threads }
+    }
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-5,
+            "{} is not {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn full_load_has_no_target() {
+        let load = LoadControl::full(4);
+        assert_eq!(load.duty(), 1.0);
+        assert_eq!(load.target(), None);
+        assert_eq!(load.active_threads(), 4);
+        assert!(load.is_active(3));
+        assert!(!load.is_active(4));
+    }
+
+    #[test]
+    fn target_sets_the_starting_duty() {
+        let load = LoadControl::with_target(40.0, 2);
+        assert_close(load.duty(), 0.4);
+        assert_eq!(load.target(), Some(40.0));
+        assert_eq!(load.active_threads(), 2);
+    }
+
+    #[test]
+    fn steady_target_corrects_part_of_the_error() {
+        let load = LoadControl::with_target(50.0, 2);
+        load.step(point(50.0, None), 40.0, 2);
+        assert_close(load.duty(), 0.5 + GAIN * 0.1);
+        load.step(point(50.0, None), 60.0, 2);
+        assert_close(load.duty(), 0.5);
+    }
+
+    #[test]
+    fn readings_above_full_load_count_as_full_load() {
+        let load = LoadControl::with_target(50.0, 1);
+        load.step(point(50.0, None), 150.0, 1);
+        assert_close(load.duty(), 0.5 - GAIN * 0.5);
+    }
+
+    #[test]
+    fn large_target_changes_settle_before_correcting() {
+        let load = LoadControl::with_target(20.0, 2);
+        // The reading still shows the old load, so only the feed-forward applies
+        load.step(point(80.0, None), 20.0, 2);
+        assert_close(load.duty(), 0.8);
+        assert_eq!(load.target(), Some(80.0));
+    }
+
+    #[test]
+    fn worker_count_changes_settle_before_correcting() {
+        let load = LoadControl::with_target(50.0, 4);
+        load.step(point(50.0, Some(2)), 10.0, 4);
+        assert_close(load.duty(), 0.5);
+        assert_eq!(load.active_threads(), 2);
+    }
+
+    #[test]
+    fn worker_count_is_capped_by_the_run() {
+        let load = LoadControl::with_target(50.0, 4);
+        load.step(point(50.0, Some(8)), 50.0, 4);
+        assert_eq!(load.active_threads(), 4);
+    }
+
+    #[test]
+    fn zero_target_idles_every_worker() {
+        let load = LoadControl::with_target(50.0, 4);
+        load.step(point(0.0, None), 50.0, 4);
+        assert_eq!(load.active_threads(), 0);
+        assert_close(load.duty(), MIN_DUTY);
+    }
+
+    #[test]
+    fn duty_stays_within_bounds() {
+        let load = LoadControl::with_target(95.0, 1);
+        load.step(point(99.0, None), 0.0, 1);
+        assert_eq!(load.duty(), 1.0);
+        let load = LoadControl::with_target(2.0, 1);
+        load.step(point(1.0, None), 100.0, 1);
+        assert_close(load.duty(), MIN_DUTY);
+    }
+
+    #[test]
+    fn mean_usage_of_no_cpus_is_zero() {
+        assert_eq!(mean_usage(&[]), 0.0);
+    }
+}
@@ -0,0 +1,73 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+
+use crate::result::{ComputationError, StressResult};
+
+/// Progress of a running stress test, as delivered to `CpuExplosion::subscribe`.
+#[derive(Clone, Debug)]
+pub enum StressEvent {
+    Started {
+        workload: String,
+        threads: usize,
+        /// Unit of the `rate` in samples and the final result.
+        rate_unit: &'static str,
+        started_at: SystemTime,
+    },
+    Sample(ThroughputSample),
+    /// A worker computed a wrong result or panicked. The run will be marked failed.
+    Error(ComputationError),
+    Finished(StressResult),
+}
+
+/// Throughput over one sampling interval.
+#[derive(Clone, Debug)]
+pub struct ThroughputSample {
+    /// Time since the start of the run at the end of the interval.
+    pub elapsed: Duration,
+    /// Work units per second of each worker, ordered by thread index.
+    pub workers: Vec<f64>,
+    /// Sum of `workers`.
+    pub total: f64,
+    /// Workload-specific rate over the interval, e.g. GFLOPS.
+    pub rate: f64,
+    /// Share of the time workers were allowed to run, 1.0 unless a target load is set.
+    pub duty: f32,
+    /// Target CPU usage in percent when following a target load or profile.
+    pub target_load: Option<f32>,
+    /// Workers not idled by the load profile.
+    pub active_threads: usize,
+    /// Hottest sensor reading in °C, where the platform has sensors.
+    pub temperature: Option<f32>,
+    /// Mean usage of the CPUs the active workers run on, in percent.
+    pub usage: f32,
+    /// Mean frequency of the CPUs the active workers run on in MHz, if reported.
+    pub frequency: Option<f64>,
+    /// Current frequency of every logical CPU in MHz, 0 where unknown.
+    pub frequencies: Vec<u64>,
+}
+
+/// Fans events out to every live subscriber.
+#[derive(Clone, Default)]
+pub(crate) struct EventSink {
+    subscribers: Arc<Mutex<Vec<UnboundedSender<StressEvent>>>>,
+}
+
+impl EventSink {
+    pub(crate) fn subscribe(&self) -> UnboundedReceiver<StressEvent> {
+        let (sender, receiver) = unbounded_channel();
+        self.subscribers.lock().unwrap().push(sender);
+        receiver
+    }
+
+    /// Sends without blocking, so workers can report from their own threads.
+    pub(crate) fn send(&self, event: StressEvent) {
+        self.subscribers
+            .lock()
+            .unwrap()
+            .retain(|subscriber| subscriber.send(event.clone()).is_ok());
+    }
+}
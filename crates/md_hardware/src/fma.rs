@@ -0,0 +1,413 @@
+use std::{hint::black_box, time::Duration};
+
+use crate::workload::Workload;
+
+/// Independent accumulators per kernel, enough to hide FMA latency on current cores.
+const ACCUMULATORS: usize = 8;
+/// Floating-point operations per batch, before rounding to whole kernel iterations.
+const BATCH_FLOPS: u64 = 1 << 24;
+/// Floating-point operations in one work unit.
+const FLOPS_PER_UNIT: u64 = 1000000;
+
+const MUL: f64 = 0.999999;
+const ADD: f64 = 0.000001;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Precision {
+    F32,
+    F64,
+}
+
+/// Vector instruction set used by the FMA kernel.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SimdLevel {
+    Portable,
+    Sse,
+    Avx2,
+    Avx512,
+}
+
+impl SimdLevel {
+    /// Picks the widest instruction set the running CPU supports.
+    pub fn detect() -> Self {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("avx512f") {
+                return SimdLevel::Avx512;
+            }
+            if is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma") {
+                return SimdLevel::Avx2;
+            }
+            SimdLevel::Sse
+        }
+        #[cfg(not(target_arch = "x86_64"))]
+        {
+            SimdLevel::Portable
+        }
+    }
+
+    /// Number of values of the given precision in one vector register.
+    fn lanes(self, precision: Precision) -> u64 {
+        let bytes = match self {
+            SimdLevel::Portable => return 1,
+            SimdLevel::Sse => 16,
+            SimdLevel::Avx2 => 32,
+            SimdLevel::Avx512 => 64,
+        };
+        match precision {
+            Precision::F32 => bytes / 4,
+            Precision::F64 => bytes / 8,
+        }
+    }
+}
+
+/// Multiply-add chains over independent accumulators, using the widest vector unit
+/// available. One work unit is a million floating-point operations and the rate is
+/// reported in GFLOPS.
+pub struct FmaWorkload {
+    precision: Precision,
+    level: SimdLevel,
+    iterations: u64,
+    pending_flops: u64,
+}
+
+impl FmaWorkload {
+    pub fn new(precision: Precision) -> Self {
+        let mut workload = FmaWorkload {
+            precision,
+            level: SimdLevel::Portable,
+            iterations: 0,
+            pending_flops: 0,
+        };
+        workload.set_level(SimdLevel::detect());
+        workload
+    }
+
+    /// Forces a specific instruction set, e.g. to compare paths. Falls back to the
+    /// portable kernel if the CPU does not support the requested one.
+    pub fn with_level(precision: Precision, level: SimdLevel) -> Self {
+        let mut workload = Self::new(precision);
+        let detected = workload.level;
+        let supported = match level {
+            SimdLevel::Portable => true,
+            SimdLevel::Sse => detected != SimdLevel::Portable,
+            SimdLevel::Avx2 => matches!(detected, SimdLevel::Avx2 | SimdLevel::Avx512),
+            SimdLevel::Avx512 => detected == SimdLevel::Avx512,
+        };
+        workload.set_level(if supported {
+            level
+        } else {
+            SimdLevel::Portable
+        });
+        workload
+    }
+
+    pub fn level(&self) -> SimdLevel {
+        self.level
+    }
+
+    fn set_level(&mut self, level: SimdLevel) {
+        self.level = level;
+        self.iterations = BATCH_FLOPS / self.flops_per_iteration();
+    }
+
+    fn flops_per_iteration(&self) -> u64 {
+        // Every lane of every accumulator does one multiply and one add per iteration.
+        ACCUMULATORS as u64 * self.level.lanes(self.precision) * 2
+    }
+}
+
+impl Workload for FmaWorkload {
+    fn run_batch(&mut self) -> Result<u64, String> {
+        let n = self.iterations;
+        // SAFETY: SSE2 is part of the x86_64 baseline, and `level` is only Avx2 or
+        // Avx512 when runtime detection found the features those kernels need.
+        match (self.level, self.precision) {
+            (SimdLevel::Portable, Precision::F32) => {
+                black_box(portable_f32(n));
+            }
+            (SimdLevel::Portable, Precision::F64) => {
+                black_box(portable_f64(n));
+            }
+            #[cfg(target_arch = "x86_64")]
+            (SimdLevel::Sse, Precision::F32) => {
+                black_box(unsafe { x86::sse_f32(n) });
+            }
+            #[cfg(target_arch = "x86_64")]
+            (SimdLevel::Sse, Precision::F64) => {
+                black_box(unsafe { x86::sse_f64(n) });
+            }
+            #[cfg(target_arch = "x86_64")]
+            (SimdLevel::Avx2, Precision::F32) => {
+                black_box(unsafe { x86::avx2_f32(n) });
+            }
+            #[cfg(target_arch = "x86_64")]
+            (SimdLevel::Avx2, Precision::F64) => {
+                black_box(unsafe { x86::avx2_f64(n) });
+            }
+            #[cfg(target_arch = "x86_64")]
+            (SimdLevel::Avx512, Precision::F32) => {
+                black_box(unsafe { x86::avx512_f32(n) });
+            }
+            #[cfg(target_arch = "x86_64")]
+            (SimdLevel::Avx512, Precision::F64) => {
+                black_box(unsafe { x86::avx512_f64(n) });
+            }
+            #[cfg(not(target_arch = "x86_64"))]
+            _ => unreachable!("only the portable kernel exists on this architecture"),
+        }
+
+        self.pending_flops += n * self.flops_per_iteration();
+        let units = self.pending_flops / FLOPS_PER_UNIT;
+        self.pending_flops %= FLOPS_PER_UNIT;
+        Ok(units)
+    }
+
+    fn rate_unit(&self) -> &'static str {
+        "GFLOPS"
+    }
+
+    fn rate(&self, units: u64, elapsed: Duration) -> f64 {
+        (units * FLOPS_PER_UNIT) as f64 / 1e9 / elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+fn portable_f32(iterations: u64) -> f32 {
+    let (mul, add) = (black_box(MUL as f32), black_box(ADD as f32));
+    let mut acc = black_box(seeds::<f32>());
+    for _ in 0..iterations {
+        for a in acc.iter_mut() {
+            *a = *a * mul + add;
+        }
+    }
+    acc.iter().sum()
+}
+
+fn portable_f64(iterations: u64) -> f64 {
+    let (mul, add) = (black_box(MUL), black_box(ADD));
+    let mut acc = black_box(seeds::<f64>());
+    for _ in 0..iterations {
+        for a in acc.iter_mut() {
+            *a = *a * mul + add;
+        }
+    }
+    acc.iter().sum()
+}
+
+/// Distinct starting values for the accumulators. They go through `black_box` at the
+/// call sites so the compiler can neither fold the chains nor merge identical ones.
+fn seeds<T: From<f32>>() -> [T; ACCUMULATORS] {
+    std::array::from_fn(|i| T::from(2.0 + i as f32 * 0.125))
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use std::arch::x86_64::*;
+
+    use std::hint::black_box;
+
+    use super::{ADD, MUL, seeds};
+
+    // Each kernel keeps `ACCUMULATORS` independent registers so consecutive
+    // multiply-adds do not wait on each other, then folds them into one value so
+    // the compiler cannot drop the work.
+
+    #[target_feature(enable = "sse2")]
+    pub fn sse_f32(iterations: u64) -> f32 {
+        let mul = _mm_set1_ps(black_box(MUL as f32));
+        let add = _mm_set1_ps(black_box(ADD as f32));
+        let mut acc = black_box(seeds::<f32>()).map(|v| _mm_set1_ps(v));
+        for _ in 0..iterations {
+            for a in acc.iter_mut() {
+                *a = _mm_add_ps(_mm_mul_ps(*a, mul), add);
+            }
+        }
+        let mut sum = _mm_setzero_ps();
+        for a in acc {
+            sum = _mm_add_ps(sum, a);
+        }
+        _mm_cvtss_f32(sum)
+    }
+
+    #[target_feature(enable = "sse2")]
+    pub fn sse_f64(iterations: u64) -> f64 {
+        let mul = _mm_set1_pd(black_box(MUL));
+        let add = _mm_set1_pd(black_box(ADD));
+        let mut acc = black_box(seeds::<f64>()).map(|v| _mm_set1_pd(v));
+        for _ in 0..iterations {
+            for a in acc.iter_mut() {
+                *a = _mm_add_pd(_mm_mul_pd(*a, mul), add);
+            }
+        }
+        let mut sum = _mm_setzero_pd();
+        for a in acc {
+            sum = _mm_add_pd(sum, a);
+        }
+        _mm_cvtsd_f64(sum)
+    }
+
+    #[target_feature(enable = "avx2,fma")]
+    pub fn avx2_f32(iterations: u64) -> f32 {
+        let mul = _mm256_set1_ps(black_box(MUL as f32));
+        let add = _mm256_set1_ps(black_box(ADD as f32));
+        let mut acc = black_box(seeds::<f32>()).map(|v| _mm256_set1_ps(v));
+        for _ in 0..iterations {
+            for a in acc.iter_mut() {
+                *a = _mm256_fmadd_ps(*a, mul, add);
+            }
+        }
+        let mut sum = _mm256_setzero_ps();
+        for a in acc {
+            sum = _mm256_add_ps(sum, a);
+        }
+        _mm256_cvtss_f32(sum)
+    }
+
+    #[target_feature(enable = "avx2,fma")]
+    pub fn avx2_f64(iterations: u64) -> f64 {
+        let mul = _mm256_set1_pd(black_box(MUL));
+        let add = _mm256_set1_pd(black_box(ADD));
+        let mut acc = black_box(seeds::<f64>()).map(|v| _mm256_set1_pd(v));
+        for _ in 0..iterations {
+            for a in acc.iter_mut() {
+                *a = _mm256_fmadd_pd(*a, mul, add);
+            }
+        }
+        let mut sum = _mm256_setzero_pd();
+        for a in acc {
+            sum = _mm256_add_pd(sum, a);
+        }
+        _mm256_cvtsd_f64(sum)
+    }
+
+    #[target_feature(enable = "avx512f")]
+    pub fn avx512_f32(iterations: u64) -> f32 {
+        let mul = _mm512_set1_ps(black_box(MUL as f32));
+        let add = _mm512_set1_ps(black_box(ADD as f32));
+        let mut acc = black_box(seeds::<f32>()).map(|v| _mm512_set1_ps(v));
+        for _ in 0..iterations {
+            for a in acc.iter_mut() {
+                *a = _mm512_fmadd_ps(*a, mul, add);
+            }
+        }
+        let mut sum = _mm512_setzero_ps();
+        for a in acc {
+            sum = _mm512_add_ps(sum, a);
+        }
+        _mm512_reduce_add_ps(sum)
+    }
+
+    #[target_feature(enable = "avx512f")]
+    pub fn avx512_f64(iterations: u64) -> f64 {
+        let mul = _mm512_set1_pd(black_box(MUL));
+        let add = _mm512_set1_pd(black_box(ADD));
+        let mut acc = black_box(seeds::<f64>()).map(|v| _mm512_set1_pd(v));
+        for _ in 0..iterations {
+            for a in acc.iter_mut() {
+                *a = _mm512_fmadd_pd(*a, mul, add);
+            }
+        }
+        let mut sum = _mm512_setzero_pd();
+        for a in acc {
+            sum = _mm512_add_pd(sum, a);
+        }
+        _mm512_reduce_add_pd(sum)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ITERATIONS: u64 = 1000;
+    /// Single precision drifts from the exact sum by rounding on every step.
+    const F32_TOLERANCE: f64 = 1e-3;
+    const F64_TOLERANCE: f64 = 1e-12;
+
+    /// Sum of the accumulators after `ITERATIONS` multiply-adds, in closed form:
+    /// every chain approaches the fixed point 1.0 as `1 + (seed - 1) * MUL^n`.
+    fn reference() -> f64 {
+        seeds::<f64>()
+            .iter()
+            .map(|seed| 1.0 + (seed - 1.0) * MUL.powi(ITERATIONS as i32))
+            .sum()
+    }
+
+    fn assert_close(kernel: &str, actual: f64, tolerance: f64) {
+        let expected = reference();
+        assert!(
+            ((actual - expected) / expected).abs() < tolerance,
+            "{} gave {}, expected {}",
+            kernel,
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn portable_kernels_match_the_reference() {
+        assert_close(
+            "portable f32",
+            portable_f32(ITERATIONS) as f64,
+            F32_TOLERANCE,
+        );
+        assert_close("portable f64", portable_f64(ITERATIONS), F64_TOLERANCE);
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn vector_kernels_match_the_reference() {
+        // SAFETY: each kernel only runs when the CPU has the features it enables
+        unsafe {
+            assert_close("sse f32", x86::sse_f32(ITERATIONS) as f64, F32_TOLERANCE);
+            assert_close("sse f64", x86::sse_f64(ITERATIONS), F64_TOLERANCE);
+            if is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma") {
+                assert_close("avx2 f32", x86::avx2_f32(ITERATIONS) as f64, F32_TOLERANCE);
+                assert_close("avx2 f64", x86::avx2_f64(ITERATIONS), F64_TOLERANCE);
+            }
+            // AVX-512 folds every lane into the result, not just the first
+            if is_x86_feature_detected!("avx512f") {
+                assert_close(
+                    "avx512 f32",
+                    x86::avx512_f32(ITERATIONS) as f64 / 16.0,
+                    F32_TOLERANCE,
+                );
+                assert_close(
+                    "avx512 f64",
+                    x86::avx512_f64(ITERATIONS) / 8.0,
+                    F64_TOLERANCE,
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn unsupported_levels_fall_back_to_portable() {
+        let detected = SimdLevel::detect();
+        for level in [SimdLevel::Sse, SimdLevel::Avx2, SimdLevel::Avx512] {
+            let workload = FmaWorkload::with_level(Precision::F64, level);
+            assert!(
+                workload.level() == level || workload.level() == SimdLevel::Portable,
+                "{:?} became {:?}",
+                level,
+                workload.level()
+            );
+            if level == detected {
+                assert_eq!(workload.level(), level);
+            }
+        }
+    }
+
+    #[test]
+    fn batches_are_counted_in_whole_units() {
+        let mut workload = FmaWorkload::with_level(Precision::F32, SimdLevel::Portable);
+        let batch_flops = workload.iterations * workload.flops_per_iteration();
+        let units = workload.run_batch().unwrap();
+        assert_eq!(units, batch_flops / FLOPS_PER_UNIT);
+        assert_eq!(workload.pending_flops, batch_flops % FLOPS_PER_UNIT);
+        assert_eq!(
+            workload.rate(1000, Duration::from_secs(1)),
+            (1000 * FLOPS_PER_UNIT) as f64 / 1e9
+        );
+    }
+}
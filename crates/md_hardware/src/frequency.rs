@@ -0,0 +1,285 @@
+use std::time::Duration;
+
+use crate::events::ThroughputSample;
+
+/// Loaded samples at the start of a run that set the baseline to compare against.
+const BASELINE_SAMPLES: usize = 5;
+/// Average usage of the workers' CPUs at which they count as fully loaded, in percent.
+const FULL_LOAD: f32 = 95.0;
+/// A frequency below this share of the baseline under full load counts as throttling.
+const FREQUENCY_DROP: f64 = 0.9;
+/// Throughput below this share of the baseline counts as throttling.
+const RATE_DROP: f64 = 0.8;
+/// Throttled samples in a row before they count, so a brief dip such as a
+/// background task taking a CPU for a moment is ignored.
+const SUSTAINED_SAMPLES: u32 = 3;
+
+/// Minimum and maximum frequency of a logical CPU in MHz, from cpufreq sysfs.
+pub fn cpufreq_limits(cpu: usize) -> (Option<u64>, Option<u64>) {
+    #[cfg(target_os = "linux")]
+    {
+        // cpufreq reports kHz
+        let read = |name: &str| {
+            let path = format!("/sys/devices/system/cpu/cpu{}/cpufreq/{}", cpu, name);
+            std::fs::read_to_string(path)
+                .ok()
+                .and_then(|s| s.trim().parse::<u64>().ok())
+                .map(|khz| khz / 1000)
+        };
+        (read("cpuinfo_min_freq"), read("cpuinfo_max_freq"))
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = cpu;
+        (None, None)
+    }
+}
+
+/// Signs of thermal or power throttling seen during a run.
+#[derive(Clone, Debug, Default)]
+pub struct ThrottleReport {
+    /// Total length of the sampling intervals that looked throttled. Only drops that
+    /// last several samples in a row count, here and in the drop counts.
+    pub throttled_for: Duration,
+    /// Time since the start of the run of the first throttled interval.
+    pub first_at: Option<Duration>,
+    /// Intervals where the workers' CPUs clocked down while fully loaded.
+    pub frequency_drops: u32,
+    /// Intervals where throughput fell well below the early rate.
+    pub rate_drops: u32,
+    /// Mean frequency of the workers' CPUs over the run, in MHz.
+    pub average_frequency: Option<f64>,
+    /// Frequency at the start of the run under full load, in MHz.
+    pub baseline_frequency: Option<f64>,
+    /// Lowest frequency seen under full load, in MHz.
+    pub lowest_frequency: Option<f64>,
+}
+
+impl ThrottleReport {
+    pub fn throttled(&self) -> bool {
+        !self.throttled_for.is_zero()
+    }
+}
+
+/// Consecutive throttled samples that have not been added to the report yet.
+#[derive(Default)]
+struct Dip {
+    started_at: Duration,
+    samples: u32,
+    length: Duration,
+    frequency_drops: u32,
+    rate_drops: u32,
+}
+
+/// Builds a `ThrottleReport` from the samples of a run.
+#[derive(Default)]
+pub(crate) struct ThrottleDetector {
+    report: ThrottleReport,
+    baseline_rate: Option<f64>,
+    baseline_samples: usize,
+    frequency_sum: f64,
+    frequency_samples: u32,
+    last_elapsed: Duration,
+    /// The current run of throttled samples, `None` while not throttled.
+    dip: Option<Dip>,
+}
+
+impl ThrottleDetector {
+    /// Only samples taken flat out on every worker are compared, as the load profile
+    /// and target load change frequency and throughput on purpose.
+    pub fn observe(&mut self, sample: &ThroughputSample, threads: usize) {
+        let interval = sample.elapsed.saturating_sub(self.last_elapsed);
+        self.last_elapsed = sample.elapsed;
+        if let Some(frequency) = sample.frequency {
+            self.frequency_sum += frequency;
+            self.frequency_samples += 1;
+            self.report.average_frequency =
+                Some(self.frequency_sum / self.frequency_samples as f64);
+        }
+
+        let flat_out = sample.target_load.is_none() && sample.active_threads == threads;
+        if !flat_out {
+            return;
+        }
+        let fully_loaded = sample.usage >= FULL_LOAD;
+        let report = &mut self.report;
+
+        if self.baseline_samples < BASELINE_SAMPLES {
+            self.baseline_samples += 1;
+            self.baseline_rate = Some(
+                self.baseline_rate
+                    .map_or(sample.total, |r| r.max(sample.total)),
+            );
+            if fully_loaded && let Some(frequency) = sample.frequency {
+                report.baseline_frequency = Some(
+                    report
+                        .baseline_frequency
+                        .map_or(frequency, |f| f.max(frequency)),
+                );
+            }
+            return;
+        }
+
+        let mut frequency_drop = false;
+        if fully_loaded && let Some(frequency) = sample.frequency {
+            report.lowest_frequency = Some(
+                report
+                    .lowest_frequency
+                    .map_or(frequency, |f| f.min(frequency)),
+            );
+            frequency_drop = report
+                .baseline_frequency
+                .is_some_and(|baseline| frequency < baseline * FREQUENCY_DROP);
+        }
+        let rate_drop = self
+            .baseline_rate
+            .is_some_and(|baseline| sample.total < baseline * RATE_DROP);
+        if !frequency_drop && !rate_drop {
+            self.dip = None;
+            return;
+        }
+
+        let dip = self.dip.get_or_insert_with(|| Dip {
+            started_at: sample.elapsed,
+            ..Dip::default()
+        });
+        dip.samples += 1;
+        dip.length += interval;
+        dip.frequency_drops += frequency_drop as u32;
+        dip.rate_drops += rate_drop as u32;
+        // Once a dip lasts, all of it counts, and every further sample straight away
+        if dip.samples >= SUSTAINED_SAMPLES {
+            report.throttled_for += std::mem::take(&mut dip.length);
+            report.frequency_drops += std::mem::take(&mut dip.frequency_drops);
+            report.rate_drops += std::mem::take(&mut dip.rate_drops);
+            report.first_at.get_or_insert(dip.started_at);
+        }
+    }
+
+    pub fn report(&self) -> ThrottleReport {
+        self.report.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const THREADS: usize = 2;
+
+    /// A flat-out sample at `secs` into the run, one second after the previous one.
+    fn sample(secs: u64, total: f64, frequency: f64) -> ThroughputSample {
+        ThroughputSample {
+            elapsed: Duration::from_secs(secs),
+            workers: vec![total / 2.0; THREADS],
+            total,
+            rate: total,
+            duty: 1.0,
+            target_load: None,
+            active_threads: THREADS,
+            temperature: None,
+            usage: 100.0,
+            frequency: Some(frequency),
+            frequencies: Vec::new(),
+        }
+    }
+
+    /// A detector past its baseline of 100 units/s at 3000 MHz, 5 seconds in.
+    fn detector() -> ThrottleDetector {
+        let mut detector = ThrottleDetector::default();
+        for secs in 1..=5 {
+            detector.observe(&sample(secs, 100.0, 3000.0), THREADS);
+        }
+        detector
+    }
+
+    #[test]
+    fn baseline_comes_from_the_first_samples() {
+        let report = detector().report();
+        assert_eq!(report.baseline_frequency, Some(3000.0));
+        assert_eq!(report.average_frequency, Some(3000.0));
+        assert!(!report.throttled());
+    }
+
+    #[test]
+    fn sustained_frequency_drop_counts() {
+        let mut detector = detector();
+        for secs in 6..=7 {
+            detector.observe(&sample(secs, 100.0, 2000.0), THREADS);
+        }
+        assert!(!detector.report().throttled());
+
+        detector.observe(&sample(8, 100.0, 2000.0), THREADS);
+        let report = detector.report();
+        // The whole dip counts once it has lasted
+        assert_eq!(report.throttled_for, Duration::from_secs(3));
+        assert_eq!(report.first_at, Some(Duration::from_secs(6)));
+        assert_eq!(report.frequency_drops, 3);
+        assert_eq!(report.rate_drops, 0);
+
+        detector.observe(&sample(9, 100.0, 2500.0), THREADS);
+        let report = detector.report();
+        assert_eq!(report.throttled_for, Duration::from_secs(4));
+        assert_eq!(report.frequency_drops, 4);
+        assert_eq!(report.lowest_frequency, Some(2000.0));
+    }
+
+    #[test]
+    fn sustained_rate_drop_counts() {
+        let mut detector = detector();
+        for secs in 6..=8 {
+            detector.observe(&sample(secs, 50.0, 3000.0), THREADS);
+        }
+        let report = detector.report();
+        assert_eq!(report.rate_drops, 3);
+        assert_eq!(report.frequency_drops, 0);
+        assert_eq!(report.throttled_for, Duration::from_secs(3));
+    }
+
+    #[test]
+    fn brief_dip_does_not_count() {
+        let mut detector = detector();
+        detector.observe(&sample(6, 50.0, 2000.0), THREADS);
+        detector.observe(&sample(7, 100.0, 2000.0), THREADS);
+        detector.observe(&sample(8, 100.0, 3000.0), THREADS);
+        detector.observe(&sample(9, 50.0, 3000.0), THREADS);
+        let report = detector.report();
+        assert!(!report.throttled());
+        assert_eq!(report.first_at, None);
+        assert_eq!((report.frequency_drops, report.rate_drops), (0, 0));
+        // The dip is still the lowest frequency seen
+        assert_eq!(report.lowest_frequency, Some(2000.0));
+    }
+
+    #[test]
+    fn recovery_stops_the_count() {
+        let mut detector = detector();
+        for secs in 6..=8 {
+            detector.observe(&sample(secs, 100.0, 2000.0), THREADS);
+        }
+        for secs in 9..=20 {
+            detector.observe(&sample(secs, 100.0, 2950.0), THREADS);
+        }
+        // A later brief dip starts over instead of extending the first one
+        detector.observe(&sample(21, 100.0, 2000.0), THREADS);
+        let report = detector.report();
+        assert_eq!(report.throttled_for, Duration::from_secs(3));
+        assert_eq!(report.frequency_drops, 3);
+        assert_eq!(report.first_at, Some(Duration::from_secs(6)));
+    }
+
+    #[test]
+    fn samples_below_full_speed_on_purpose_are_not_compared() {
+        let mut detector = detector();
+        for secs in 6..=10 {
+            let mut held = sample(secs, 40.0, 1500.0);
+            held.target_load = Some(40.0);
+            detector.observe(&held, THREADS);
+        }
+        let report = detector.report();
+        assert!(!report.throttled());
+        assert_eq!(report.lowest_frequency, None);
+        // The average still covers every sample
+        assert_eq!(report.average_frequency, Some(2250.0));
+    }
+}
@@ -1,15 +1,90 @@
 // main.rs
-use std::{sync::{atomic::{AtomicBool, AtomicU64, Ordering}, Arc}, time::Instant};
-use sysinfo::{RefreshKind, System};
-use tokio::task::JoinSet;
+use std::{sync::{atomic::{AtomicBool, AtomicU64, Ordering}, Arc, Mutex}, time::{Duration, Instant, SystemTime}};
+use sysinfo::{Components, RefreshKind, System};
+use thermal::ThermalWatch;
+use tokio::sync::mpsc::UnboundedReceiver;
+
+use duty::{follow_profile, mean_usage, LoadControl};
+use frequency::ThrottleDetector;
+use events::EventSink;
+use worker::{run_workload_blocking, RunState};
+
+mod affinity;
+mod config;
+mod duty;
+mod events;
+mod frequency;
+mod fma;
+mod memory;
+mod memtest;
+mod pause;
+mod pool;
+mod profile;
+mod result;
+mod thermal;
+mod torture;
+mod worker;
+mod workload;
+
+pub use affinity::{CpuAffinity, current_cpu, logical_cpu_count, physical_core_cpus, pin_current_thread, unpin_current_thread};
+pub use config::StressConfig;
+pub use events::{StressEvent, ThroughputSample};
+pub use fma::{FmaWorkload, Precision, SimdLevel};
+pub use frequency::{ThrottleReport, cpufreq_limits};
+pub use memory::{BandwidthReport, KernelRates, MemoryBandwidth, ThreadBandwidth, last_level_cache_bytes};
+pub use memtest::{MemTest, MemTestReport, MemoryError, Pattern};
+pub use pause::PauseControl;
+pub use pool::{StressPool, WorkerPriority};
+pub use profile::{LoadProfile, MAX_TIME, ProfilePoint, Segment, Shape, parse_time};
+pub use result::{ComputationError, StressResult, WorkerResult};
+pub use thermal::{ThermalAbort, ThermalLimit, hottest_temperature};
+pub use torture::Torture;
+pub use workload::{Fibonacci, Workload, WorkloadFactory, WorkloadRegistry};
 
 pub struct SystemUsage {
     system: System,
+    components: Components,
+    /// cpufreq (min, max) in MHz per logical CPU, read once as they do not change.
+    frequency_limits: Vec<(Option<u64>, Option<u64>)>,
 }
 
 pub struct CpuUsage {
     pub usage: f32,
-    pub name: String
+    pub name: String,
+    /// Current frequency in MHz, 0 where the platform does not report it.
+    pub frequency: u64,
+    /// Lowest and highest frequency the CPU supports in MHz, from cpufreq where available.
+    pub min_frequency: Option<u64>,
+    pub max_frequency: Option<u64>,
+}
+
+/// A temperature sensor, such as a CPU package or core. Values are in °C and `None`
+/// where the sensor does not report them.
+#[derive(Clone, Debug)]
+pub struct Temperature {
+    pub label: String,
+    pub current: Option<f32>,
+    /// Highest value seen since monitoring started.
+    pub max: Option<f32>,
+    /// Threshold the hardware considers critical.
+    pub critical: Option<f32>,
+}
+
+/// Identifies the machine a run was made on, so saved results can be told apart.
+#[derive(Clone, Debug)]
+pub struct HardwareInfo {
+    pub hostname: String,
+    pub os: String,
+    pub kernel: String,
+    pub arch: String,
+    pub cpu_brand: String,
+    pub cpu_vendor: String,
+    pub logical_cores: usize,
+    pub physical_cores: Option<usize>,
+    /// Total RAM in bytes.
+    pub total_memory: u64,
+    /// Highest cpufreq limit of any CPU in MHz, where available.
+    pub max_frequency: Option<u64>,
 }
 
 impl SystemUsage {
@@ -17,7 +92,10 @@ impl SystemUsage {
         let mut system = System::new_with_specifics(RefreshKind::everything());
         system.refresh_all();
 
-        Self { system }
+        let components = Components::new_with_refreshed_list();
+        let frequency_limits = (0..system.cpus().len()).map(cpufreq_limits).collect();
+
+        Self { system, components, frequency_limits }
     }
 
     pub fn get_cpu_info(&mut self) -> (usize, Vec<CpuUsage>) {
@@ -26,11 +104,15 @@ impl SystemUsage {
         let logical_cores = cpus.len();
         let mut res: Vec<CpuUsage> = Vec::new();
 
-        for cpu in cpus {
+        for (i, cpu) in cpus.iter().enumerate() {
+            let (min_frequency, max_frequency) = self.frequency_limits.get(i).copied().unwrap_or_default();
             res.push(
                 CpuUsage {
                     usage: cpu.cpu_usage(),
-                    name: cpu.name().to_owned()
+                    name: cpu.name().to_owned(),
+                    frequency: cpu.frequency(),
+                    min_frequency,
+                    max_frequency,
                 }
             );
         }
@@ -42,82 +124,302 @@ impl SystemUsage {
         self.system.refresh_memory();
         (self.system.used_memory(), self.system.total_memory())
     }
+
+    pub fn hardware_info(&self) -> HardwareInfo {
+        let first_cpu = self.system.cpus().first();
+        HardwareInfo {
+            hostname: System::host_name().unwrap_or_default(),
+            os: System::long_os_version().unwrap_or_default(),
+            kernel: System::kernel_version().unwrap_or_default(),
+            arch: System::cpu_arch(),
+            cpu_brand: first_cpu.map(|cpu| cpu.brand().trim().to_owned()).unwrap_or_default(),
+            cpu_vendor: first_cpu.map(|cpu| cpu.vendor_id().to_owned()).unwrap_or_default(),
+            logical_cores: self.system.cpus().len(),
+            physical_cores: System::physical_core_count(),
+            total_memory: self.system.total_memory(),
+            max_frequency: self.frequency_limits.iter().filter_map(|&(_, max)| max).max(),
+        }
+    }
+
+    /// Readings of every temperature sensor (hwmon on Linux). Empty where the
+    /// platform exposes none, e.g. in most VMs.
+    pub fn get_temperatures(&mut self) -> Vec<Temperature> {
+        self.components.refresh(false);
+        self.components
+            .list()
+            .iter()
+            .map(|component| Temperature {
+                label: component.label().to_owned(),
+                current: component.temperature(),
+                max: component.max(),
+                critical: component.critical(),
+            })
+            .collect()
+    }
+}
+
+impl Default for SystemUsage {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-const SCORE_UNIT: u64 = 1000000;
 #[derive(Clone)]
 pub struct CpuExplosion {
-    pub stop_signal: Arc<AtomicBool>
+    pub stop_signal: Arc<AtomicBool>,
+    pub pause: PauseControl,
+    workloads: Arc<WorkloadRegistry>,
+    pool: Arc<Mutex<StressPool>>,
+    events: EventSink,
 }
 
 impl CpuExplosion {
     pub fn new() -> Self {
-        CpuExplosion { stop_signal: Arc::new(AtomicBool::new(false)) }
+        Self::with_workloads(WorkloadRegistry::new())
     }
-    pub async fn stress_test_cpu(&self, duration_sec: u64, cpu_cores: usize) -> u64 {
-        let mut handles = JoinSet::new();
-        let score = Arc::new(AtomicU64::new(0));
-        let start_time = Arc::new(Instant::now());
-
-        for _ in 0..cpu_cores {
-            let stop_signal_clone = Arc::clone(&self.stop_signal);
-            let score_clone = Arc::clone(&score);
-            let start_time_clone = Arc::clone(&start_time);
 
-            // Use spawn_blocking for CPU-bound work
-            let _ = handles.spawn_blocking(move || {
-                fibonnaci_compute_blocking(start_time_clone, duration_sec, score_clone, stop_signal_clone)
-            });
+    /// Creates a stress tester that dispatches to the given workloads.
+    pub fn with_workloads(workloads: WorkloadRegistry) -> Self {
+        CpuExplosion {
+            stop_signal: Arc::new(AtomicBool::new(false)),
+            pause: PauseControl::new(),
+            workloads: Arc::new(workloads),
+            pool: Arc::new(Mutex::new(StressPool::new())),
+            events: EventSink::default(),
         }
+    }
 
-        while let Some(res) = handles.join_next().await {
-            match res {
-                Ok(_) => {},
-                Err(e) => eprintln!("A task panicked: {:?}", e),
-            }
+    /// Creates a stress tester for a new run with a fresh stop signal and pause
+    /// control, sharing this one's workloads and worker threads.
+    pub fn next_run(&self) -> Self {
+        CpuExplosion {
+            stop_signal: Arc::new(AtomicBool::new(false)),
+            pause: PauseControl::new(),
+            workloads: Arc::clone(&self.workloads),
+            pool: Arc::clone(&self.pool),
+            events: EventSink::default(),
         }
+    }
 
-        let final_score = score.load(Ordering::Relaxed);
-        println!("CPU Stress Test Finished. Total Fibonacci computations: {}", final_score);
+    pub fn workloads(&self) -> &WorkloadRegistry {
+        &self.workloads
+    }
 
-        return final_score
+    /// The threads stress workers run on. They are started on demand and kept for
+    /// later runs until stopped.
+    pub fn pool(&self) -> &Mutex<StressPool> {
+        &self.pool
     }
-}
 
-fn fibonnaci_compute_blocking(start_time: Arc<Instant>, duration: u64, score: Arc<AtomicU64>, stop_signal: Arc<AtomicBool>){
-    let mut a: u64 = 0;
-    let mut b: u64 = 1;
-    let mut local_score = 0;
-    let mut converted_score = 0;
+    /// A memory bandwidth test on this tester's worker threads, stopped by its stop
+    /// signal.
+    pub fn memory_bandwidth(&self) -> MemoryBandwidth {
+        MemoryBandwidth::with_pool(Arc::clone(&self.pool), Arc::clone(&self.stop_signal))
+    }
 
-    loop {
-        if stop_signal.load(Ordering::Relaxed){
-            break;
+    /// Streams the events of this tester's runs: start, periodic throughput samples,
+    /// errors and the final result.
+    pub fn subscribe(&self) -> UnboundedReceiver<StressEvent> {
+        self.events.subscribe()
+    }
+
+    pub async fn stress_test_cpu(&self, config: &StressConfig) -> Result<StressResult, String> {
+        let workload = config.workload.as_str();
+        if !self.workloads.contains(workload) {
+            return Err(format!("Unknown workload: {}", workload));
+        }
+        if config.threads == 0 {
+            return Err("A stress test needs at least one thread".to_string());
         }
-        if start_time.elapsed().as_secs() >= duration {
-            stop_signal.store(true, Ordering::Relaxed);
-            break;
+        let pinned_cpus = config.affinity.resolve(logical_cpu_count())?;
+        // The CPU each worker gets, wrapping around the pinned list
+        let worker_cpus: Option<Vec<usize>> = pinned_cpus
+            .as_ref()
+            .map(|cpus| (0..config.threads).map(|i| cpus[i % cpus.len()]).collect());
+        if let Some(target) = config.target_load && !(target > 0.0 && target <= 100.0) {
+            return Err(format!("Target load must be between 0 and 100%, got {}", target));
         }
+        // A constant target load is a profile with a single segment
+        let profile = config.profile.clone().or_else(|| {
+            config.target_load.map(|target| {
+                LoadProfile::new().then(Shape::Hold(target), Duration::from_secs(config.duration_sec), None)
+            })
+        });
+        let load = match profile.as_ref().and_then(|profile| profile.at(Duration::ZERO)) {
+            Some(start) if start.load > 0.0 => {
+                LoadControl::with_target(start.load, start.threads.unwrap_or(config.threads).min(config.threads))
+            }
+            Some(start) => LoadControl::with_target(start.load, 0),
+            None => LoadControl::full(config.threads),
+        };
 
-        let next = a.checked_add(b);
+        let reporter = self.workloads.create(workload).expect("workload is registered");
+        let mut handles = Vec::new();
+        let (started_at, run) = {
+            let mut pool = self.pool.lock().unwrap();
+            // Spawn any missing threads before the clock starts
+            pool.start(config.threads);
 
-        match next {
-            Some(n) => {
-                a = b;
-                b = n;
-            },
-            None => {
-                a = 0;
-                b = 1;
+            let started_at = SystemTime::now();
+            let run = Arc::new(RunState {
+                start_time: Instant::now(),
+                duration: Duration::from_secs(config.duration_sec),
+                stop_signal: Arc::clone(&self.stop_signal),
+                pause: self.pause.clone(),
+                events: self.events.clone(),
+                progress: (0..config.threads).map(|_| AtomicU64::new(0)).collect(),
+                load,
+                cpus: worker_cpus.clone(),
+                throttling: Mutex::new(ThrottleDetector::default()),
+                thermal_abort: Mutex::new(None),
+                peak_temperature: Mutex::new(None),
+            });
+            self.events.send(StressEvent::Started {
+                workload: workload.to_owned(),
+                threads: config.threads,
+                rate_unit: reporter.rate_unit(),
+                started_at,
+            });
+            for thread_index in 0..config.threads {
+                let run_clone = Arc::clone(&run);
+                let worker = self.workloads.create(workload).expect("workload is registered");
+                let pin = worker_cpus.as_ref().map(|cpus| cpus[thread_index]);
+
+                handles.push(pool.run(thread_index, pin, config.priority, move |pinned_cpu, priority| {
+                    run_workload_blocking(worker, thread_index, pinned_cpu, priority, run_clone)
+                }));
             }
+            (started_at, run)
+        };
+
+        let sampler = tokio::spawn(sample_throughput(
+            Arc::clone(&run),
+            self.workloads.create(workload).expect("workload is registered"),
+            config.sample_interval,
+            config.thermal_limit.clone().map(ThermalWatch::new),
+        ));
+
+        let controller = profile.map(|profile| tokio::spawn(follow_profile(Arc::clone(&run), profile, config.threads)));
+
+        let mut workers = Vec::new();
+        let mut errors = Vec::new();
+        for (thread_index, handle) in handles.into_iter().enumerate() {
+            match handle.await {
+                Ok((worker, error)) => {
+                    workers.push(worker);
+                    errors.extend(error);
+                }
+                Err(_) => {
+                    let error = ComputationError {
+                        thread_index,
+                        core: None,
+                        at: run.elapsed(),
+                        message: "worker panicked".to_string(),
+                    };
+                    self.events.send(StressEvent::Error(error.clone()));
+                    errors.push(error);
+                }
+            }
+        }
+        sampler.abort();
+        if let Some(controller) = controller {
+            controller.abort();
         }
-        local_score += 1;
-        if local_score >= SCORE_UNIT {
-            converted_score += 1;
-            local_score = 0;
+        workers.sort_by_key(|w| w.thread_index);
+        errors.sort_by_key(|e| e.at);
+
+        let elapsed = run.elapsed();
+        for worker in &mut workers {
+            worker.rate = reporter.rate(worker.score, elapsed);
         }
+        let final_score = workers.iter().map(|w| w.score).sum();
+        let result = StressResult {
+            workload: workload.to_owned(),
+            score: final_score,
+            rate: reporter.rate(final_score, elapsed),
+            rate_unit: reporter.rate_unit(),
+            workers,
+            started_at,
+            finished_at: SystemTime::now(),
+            elapsed,
+            errors,
+            thermal_abort: run.thermal_abort.lock().unwrap().take(),
+            peak_temperature: *run.peak_temperature.lock().unwrap(),
+            throttling: run.throttling.lock().unwrap().report(),
+        };
+        self.events.send(StressEvent::Finished(result.clone()));
+
+        Ok(result)
     }
+}
+
+impl Default for CpuExplosion {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Publishes per-worker throughput every `interval` until aborted, and stops the run
+/// if `thermal_watch` finds the machine overheating.
+async fn sample_throughput(
+    run: Arc<RunState>,
+    reporter: Box<dyn Workload>,
+    interval: Duration,
+    mut thermal_watch: Option<ThermalWatch>,
+) {
+    let mut system_usage = SystemUsage::new();
+    let mut ticker = tokio::time::interval(interval);
+    ticker.tick().await;
+    let mut last_units: Vec<u64> = vec![0; run.progress.len()];
+    let mut last_elapsed = Duration::ZERO;
 
-    let cur_value = score.load(Ordering::Relaxed);
-    score.store(cur_value + converted_score, Ordering::Relaxed);
+    loop {
+        ticker.tick().await;
+        // Temperatures are watched even while paused
+        let temperatures = system_usage.get_temperatures();
+        let (_, usage) = system_usage.get_cpu_info();
+        let temperature = hottest_temperature(&temperatures);
+        if let Some(temperature) = temperature {
+            let mut peak = run.peak_temperature.lock().unwrap();
+            *peak = Some(peak.map_or(temperature, |peak| peak.max(temperature)));
+        }
+        if let Some(watch) = &mut thermal_watch
+            && let Some(abort) = watch.check(&temperatures, run.elapsed())
+        {
+            *run.thermal_abort.lock().unwrap() = Some(abort);
+            run.stop_signal.store(true, Ordering::Relaxed);
+            return;
+        }
+        if run.pause.is_paused() {
+            continue;
+        }
+        let elapsed = run.elapsed();
+        let secs = (elapsed - last_elapsed).as_secs_f64().max(f64::EPSILON);
+        let mut workers = Vec::with_capacity(last_units.len());
+        let mut interval_units = 0;
+        for (last, progress) in last_units.iter_mut().zip(&run.progress) {
+            let units = progress.load(Ordering::Relaxed);
+            workers.push((units - *last) as f64 / secs);
+            interval_units += units - *last;
+            *last = units;
+        }
+        let cpus = run.worker_cpus(&usage);
+        let reported: Vec<f64> = cpus.iter().filter(|cpu| cpu.frequency > 0).map(|cpu| cpu.frequency as f64).collect();
+        let sample = ThroughputSample {
+            elapsed,
+            total: workers.iter().sum(),
+            workers,
+            rate: reporter.rate(interval_units, elapsed - last_elapsed),
+            duty: run.load.duty(),
+            target_load: run.load.target(),
+            active_threads: run.load.active_threads(),
+            temperature,
+            usage: mean_usage(&cpus),
+            frequency: (!reported.is_empty()).then(|| reported.iter().sum::<f64>() / reported.len() as f64),
+            frequencies: usage.iter().map(|cpu| cpu.frequency).collect(),
+        };
+        run.throttling.lock().unwrap().observe(&sample, run.progress.len());
+        run.events.send(StressEvent::Sample(sample));
+        last_elapsed = elapsed;
+    }
 }
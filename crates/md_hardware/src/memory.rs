@@ -0,0 +1,236 @@
+use std::{
+    hint::black_box,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use crate::pool::{StressPool, WorkerPriority};
+
+/// Last-level cache size assumed when the platform does not report one.
+const DEFAULT_LLC_BYTES: usize = 32 * 1024 * 1024;
+/// Each STREAM array, summed over all threads, is at least this many times the LLC.
+const LLC_MULTIPLE: usize = 4;
+const SCALAR: f64 = 3.0;
+
+/// Bandwidth of the four STREAM kernels, in GB/s.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct KernelRates {
+    pub copy: f64,
+    pub scale: f64,
+    pub add: f64,
+    pub triad: f64,
+}
+
+impl KernelRates {
+    pub fn average(&self) -> f64 {
+        (self.copy + self.scale + self.add + self.triad) / 4.0
+    }
+
+    fn accumulate(&mut self, other: &KernelRates) {
+        self.copy += other.copy;
+        self.scale += other.scale;
+        self.add += other.add;
+        self.triad += other.triad;
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ThreadBandwidth {
+    pub thread_index: usize,
+    pub rates: KernelRates,
+}
+
+#[derive(Clone, Debug)]
+pub struct BandwidthReport {
+    pub threads: Vec<ThreadBandwidth>,
+    /// Sum of the per-thread rates, i.e. what the memory subsystem sustained.
+    pub total: KernelRates,
+    /// Bytes allocated per array on each thread.
+    pub array_bytes: usize,
+    pub llc_bytes: usize,
+    /// Threads that did not report back, e.g. because they panicked. Their bandwidth
+    /// is missing from `total`.
+    pub errors: Vec<String>,
+}
+
+/// Streams copy/scale/add/triad over arrays much larger than the last-level cache, so
+/// every access goes to DRAM. Runs on the threads of a `StressPool`.
+#[derive(Clone)]
+pub struct MemoryBandwidth {
+    pub stop_signal: Arc<AtomicBool>,
+    pool: Arc<Mutex<StressPool>>,
+}
+
+impl MemoryBandwidth {
+    /// A bandwidth test with worker threads of its own.
+    pub fn new() -> Self {
+        Self::with_pool(
+            Arc::new(Mutex::new(StressPool::new())),
+            Arc::new(AtomicBool::new(false)),
+        )
+    }
+
+    /// A bandwidth test running on `pool`'s threads and stopped by `stop_signal`, see
+    /// `CpuExplosion::memory_bandwidth`.
+    pub fn with_pool(pool: Arc<Mutex<StressPool>>, stop_signal: Arc<AtomicBool>) -> Self {
+        MemoryBandwidth { stop_signal, pool }
+    }
+
+    pub async fn stress_test_memory(&self, duration_sec: u64, threads: usize) -> BandwidthReport {
+        let threads = threads.max(1);
+        let llc_bytes = last_level_cache_bytes();
+        let array_len = (llc_bytes * LLC_MULTIPLE / threads / size_of::<f64>()).max(1024);
+
+        let start_time = Instant::now();
+        let duration = Duration::from_secs(duration_sec);
+        let handles: Vec<_> = {
+            let mut pool = self.pool.lock().unwrap();
+            pool.start(threads);
+            (0..threads)
+                .map(|thread_index| {
+                    let stop_signal = Arc::clone(&self.stop_signal);
+                    pool.run(thread_index, None, WorkerPriority::Normal, move |_, _| {
+                        stream_blocking(array_len, start_time, duration, stop_signal)
+                    })
+                })
+                .collect()
+        };
+
+        let mut results = Vec::new();
+        let mut errors = Vec::new();
+        for (thread_index, handle) in handles.into_iter().enumerate() {
+            match handle.await {
+                Ok(rates) => results.push(ThreadBandwidth {
+                    thread_index,
+                    rates,
+                }),
+                Err(_) => errors.push(format!("Bandwidth thread {} panicked", thread_index)),
+            }
+        }
+
+        let mut total = KernelRates::default();
+        for thread in &results {
+            total.accumulate(&thread.rates);
+        }
+
+        BandwidthReport {
+            threads: results,
+            total,
+            array_bytes: array_len * size_of::<f64>(),
+            llc_bytes,
+            errors,
+        }
+    }
+}
+
+impl Default for MemoryBandwidth {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Size of the largest CPU cache, used to size buffers so they cannot be cached.
+pub fn last_level_cache_bytes() -> usize {
+    #[cfg(target_os = "linux")]
+    {
+        let mut largest = 0;
+        if let Ok(entries) = std::fs::read_dir("/sys/devices/system/cpu/cpu0/cache") {
+            for entry in entries.flatten() {
+                let size = std::fs::read_to_string(entry.path().join("size"))
+                    .ok()
+                    .and_then(|s| parse_cache_size(s.trim()));
+                if let Some(size) = size {
+                    largest = largest.max(size);
+                }
+            }
+        }
+        if largest > 0 {
+            return largest;
+        }
+    }
+    DEFAULT_LLC_BYTES
+}
+
+/// Parses sysfs cache sizes such as "32768K" or "8M".
+#[cfg(target_os = "linux")]
+fn parse_cache_size(size: &str) -> Option<usize> {
+    let (digits, multiplier) = match size.chars().last()? {
+        'K' => (&size[..size.len() - 1], 1024),
+        'M' => (&size[..size.len() - 1], 1024 * 1024),
+        'G' => (&size[..size.len() - 1], 1024 * 1024 * 1024),
+        _ => (size, 1),
+    };
+    digits.parse::<usize>().ok().map(|n| n * multiplier)
+}
+
+fn stream_blocking(
+    len: usize,
+    start_time: Instant,
+    duration: Duration,
+    stop_signal: Arc<AtomicBool>,
+) -> KernelRates {
+    let mut a = vec![1.0f64; len];
+    let mut b = vec![2.0f64; len];
+    let mut c = vec![0.0f64; len];
+
+    // Bytes moved per element: two arrays touched for copy/scale, three for add/triad.
+    let bytes2 = (2 * len * size_of::<f64>()) as f64;
+    let bytes3 = (3 * len * size_of::<f64>()) as f64;
+    let mut secs = [0f64; 4];
+    let mut passes = 0u64;
+
+    loop {
+        if stop_signal.load(Ordering::Relaxed) {
+            break;
+        }
+        if start_time.elapsed() >= duration {
+            stop_signal.store(true, Ordering::Relaxed);
+            break;
+        }
+
+        let t = Instant::now();
+        c.copy_from_slice(&a);
+        black_box(&mut c);
+        secs[0] += t.elapsed().as_secs_f64();
+
+        let t = Instant::now();
+        for (b, c) in b.iter_mut().zip(&c) {
+            *b = SCALAR * c;
+        }
+        black_box(&mut b);
+        secs[1] += t.elapsed().as_secs_f64();
+
+        let t = Instant::now();
+        for ((c, a), b) in c.iter_mut().zip(&a).zip(&b) {
+            *c = a + b;
+        }
+        black_box(&mut c);
+        secs[2] += t.elapsed().as_secs_f64();
+
+        let t = Instant::now();
+        for ((a, b), c) in a.iter_mut().zip(&b).zip(&c) {
+            *a = b + SCALAR * c;
+        }
+        black_box(&mut a);
+        secs[3] += t.elapsed().as_secs_f64();
+
+        passes += 1;
+    }
+
+    let rate = |bytes: f64, secs: f64| {
+        if secs > 0.0 {
+            bytes * passes as f64 / secs / 1e9
+        } else {
+            0.0
+        }
+    };
+    KernelRates {
+        copy: rate(bytes2, secs[0]),
+        scale: rate(bytes2, secs[1]),
+        add: rate(bytes3, secs[2]),
+        triad: rate(bytes3, secs[3]),
+    }
+}
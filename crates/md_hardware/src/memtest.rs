@@ -0,0 +1,423 @@
+use std::{
+    ptr,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use crate::SystemUsage;
+
+/// Mismatches kept in the report. Further ones are only counted.
+const MAX_REPORTED_ERRORS: usize = 1000;
+/// Words written or verified between checks for a stop, so large buffers stop quickly.
+const STOP_CHECK_WORDS: usize = 1 << 16;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Pattern {
+    /// A single set bit that moves one position per word and per pass.
+    WalkingOnes,
+    /// A single cleared bit that moves one position per word and per pass.
+    WalkingZeros,
+    /// Pseudo-random words from a per-pass seed.
+    Random,
+    /// Every word holds its own address.
+    AddressInAddress,
+}
+
+impl Pattern {
+    pub const ALL: [Pattern; 4] = [
+        Pattern::WalkingOnes,
+        Pattern::WalkingZeros,
+        Pattern::Random,
+        Pattern::AddressInAddress,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Pattern::WalkingOnes => "walking ones",
+            Pattern::WalkingZeros => "walking zeros",
+            Pattern::Random => "random",
+            Pattern::AddressInAddress => "address in address",
+        }
+    }
+}
+
+/// A single bit that read back differently from what was written.
+#[derive(Clone, Debug)]
+pub struct MemoryError {
+    pub pattern: Pattern,
+    pub pass: u64,
+    /// Byte offset of the failing word from the start of the tested region.
+    pub offset: usize,
+    /// Bit position within the 64-bit word, 0 being the least significant.
+    pub bit: u32,
+    pub expected: u64,
+    pub actual: u64,
+}
+
+pub struct MemTestReport {
+    pub bytes_tested: usize,
+    /// Complete passes over every pattern.
+    pub passes: u64,
+    /// The first mismatches found, up to a fixed limit.
+    pub errors: Vec<MemoryError>,
+    pub error_count: u64,
+}
+
+impl MemTestReport {
+    /// At least one complete pass and no errors.
+    pub fn passed(&self) -> bool {
+        self.passes > 0 && self.error_count == 0
+    }
+
+    /// Stopped before a complete pass without finding errors, so it says nothing
+    /// about the memory.
+    pub fn inconclusive(&self) -> bool {
+        self.passes == 0 && self.error_count == 0
+    }
+}
+
+/// Writes memtest-style patterns over a share of free RAM and verifies them.
+#[derive(Clone)]
+pub struct MemTest {
+    pub stop_signal: Arc<AtomicBool>,
+}
+
+impl MemTest {
+    pub fn new() -> Self {
+        MemTest {
+            stop_signal: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Tests `share` (0.0 to 1.0) of the currently free memory for `duration_sec`,
+    /// splitting the region across `threads`. Fails if the memory cannot be allocated.
+    pub async fn test_memory(
+        &self,
+        share: f64,
+        duration_sec: u64,
+        threads: usize,
+    ) -> Result<MemTestReport, String> {
+        let (used, total) = SystemUsage::new().get_ram_info();
+        let bytes = (total.saturating_sub(used) as f64 * share.clamp(0.0, 1.0)) as usize;
+        let words = bytes / size_of::<u64>();
+        if words == 0 {
+            return Err("No free memory to test".to_string());
+        }
+
+        let stop_signal = Arc::clone(&self.stop_signal);
+        let threads = threads.max(1);
+        tokio::task::spawn_blocking(move || {
+            let mut buffer: Vec<u64> = Vec::new();
+            buffer
+                .try_reserve_exact(words)
+                .map_err(|e| format!("Could not allocate {} MB: {}", bytes / 1024 / 1024, e))?;
+            buffer.resize(words, 0);
+            Ok(memtest_blocking(
+                &mut buffer,
+                threads,
+                Duration::from_secs(duration_sec),
+                stop_signal,
+            ))
+        })
+        .await
+        .map_err(|e| format!("Memory test panicked: {:?}", e))?
+    }
+}
+
+impl Default for MemTest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether a test should stop: stopped by the caller or out of time. Running out of
+/// time raises the stop signal too.
+struct StopCheck {
+    stop_signal: Arc<AtomicBool>,
+    start_time: Instant,
+    duration: Duration,
+}
+
+impl StopCheck {
+    fn stopped(&self) -> bool {
+        if self.stop_signal.load(Ordering::Relaxed) {
+            return true;
+        }
+        if self.start_time.elapsed() >= self.duration {
+            self.stop_signal.store(true, Ordering::Relaxed);
+            return true;
+        }
+        false
+    }
+}
+
+fn memtest_blocking(
+    buffer: &mut [u64],
+    threads: usize,
+    duration: Duration,
+    stop_signal: Arc<AtomicBool>,
+) -> MemTestReport {
+    let stop = StopCheck {
+        stop_signal,
+        start_time: Instant::now(),
+        duration,
+    };
+    let errors = Mutex::new((Vec::new(), 0u64));
+    let chunk_words = buffer.len().div_ceil(threads);
+    let mut passes = 0;
+
+    'run: loop {
+        for pattern in Pattern::ALL {
+            if stop.stopped() {
+                break 'run;
+            }
+
+            let cut_short = AtomicBool::new(false);
+            std::thread::scope(|scope| {
+                for (i, chunk) in buffer.chunks_mut(chunk_words).enumerate() {
+                    let (errors, stop, cut_short) = (&errors, &stop, &cut_short);
+                    scope.spawn(move || {
+                        let first_word = i * chunk_words;
+                        // A partly written chunk would verify as errors
+                        let finished = write_pattern(chunk, first_word, pattern, passes, stop)
+                            && verify_pattern(chunk, first_word, pattern, passes, errors, stop);
+                        if !finished {
+                            cut_short.store(true, Ordering::Relaxed);
+                        }
+                    });
+                }
+            });
+            // A pass cut short by a stop does not count
+            if cut_short.into_inner() {
+                break 'run;
+            }
+        }
+        passes += 1;
+    }
+
+    let (errors, error_count) = errors.into_inner().unwrap();
+    MemTestReport {
+        bytes_tested: size_of_val(buffer),
+        passes,
+        errors,
+        error_count,
+    }
+}
+
+/// Value a word should hold. `index` counts words from the start of the region.
+fn expected_word(pattern: Pattern, pass: u64, index: usize, address: usize) -> u64 {
+    match pattern {
+        Pattern::WalkingOnes => 1 << ((index as u64 + pass) % 64),
+        Pattern::WalkingZeros => !(1 << ((index as u64 + pass) % 64)),
+        Pattern::Random => splitmix64(index as u64 ^ pass.rotate_left(48)),
+        Pattern::AddressInAddress => address as u64,
+    }
+}
+
+/// Fills `chunk` with the pattern. Returns false if stopped before the end.
+fn write_pattern(
+    chunk: &mut [u64],
+    first_word: usize,
+    pattern: Pattern,
+    pass: u64,
+    stop: &StopCheck,
+) -> bool {
+    for (i, word) in chunk.iter_mut().enumerate() {
+        if i % STOP_CHECK_WORDS == 0 && stop.stopped() {
+            return false;
+        }
+        let value = expected_word(pattern, pass, first_word + i, word as *mut u64 as usize);
+        // Volatile so the compiler cannot skip the store-then-load round trip.
+        unsafe { ptr::write_volatile(word, value) };
+    }
+    true
+}
+
+/// Checks `chunk` against the pattern and records every flipped bit. Returns false if
+/// stopped before the end.
+fn verify_pattern(
+    chunk: &[u64],
+    first_word: usize,
+    pattern: Pattern,
+    pass: u64,
+    errors: &Mutex<(Vec<MemoryError>, u64)>,
+    stop: &StopCheck,
+) -> bool {
+    for (i, word) in chunk.iter().enumerate() {
+        if i % STOP_CHECK_WORDS == 0 && stop.stopped() {
+            return false;
+        }
+        let expected = expected_word(pattern, pass, first_word + i, word as *const u64 as usize);
+        let actual = unsafe { ptr::read_volatile(word) };
+        let mut diff = expected ^ actual;
+        if diff == 0 {
+            continue;
+        }
+
+        let mut errors = errors.lock().unwrap();
+        while diff != 0 {
+            let bit = diff.trailing_zeros();
+            diff &= diff - 1;
+            errors.1 += 1;
+            if errors.0.len() < MAX_REPORTED_ERRORS {
+                errors.0.push(MemoryError {
+                    pattern,
+                    pass,
+                    offset: (first_word + i) * size_of::<u64>(),
+                    bit,
+                    expected,
+                    actual,
+                });
+            }
+        }
+    }
+    true
+}
+
+/// Small, fast generator so the random pattern can be recomputed for verification.
+fn splitmix64(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9E3779B97F4A7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn running() -> StopCheck {
+        StopCheck {
+            stop_signal: Arc::new(AtomicBool::new(false)),
+            start_time: Instant::now(),
+            duration: Duration::MAX,
+        }
+    }
+
+    fn no_errors() -> Mutex<(Vec<MemoryError>, u64)> {
+        Mutex::new((Vec::new(), 0))
+    }
+
+    #[test]
+    fn walking_ones_moves_one_bit_per_word_and_pass() {
+        assert_eq!(expected_word(Pattern::WalkingOnes, 0, 0, 0), 1);
+        assert_eq!(expected_word(Pattern::WalkingOnes, 0, 5, 0), 1 << 5);
+        assert_eq!(expected_word(Pattern::WalkingOnes, 2, 5, 0), 1 << 7);
+        assert_eq!(expected_word(Pattern::WalkingOnes, 1, 63, 0), 1);
+    }
+
+    #[test]
+    fn walking_zeros_is_the_inverse_of_walking_ones() {
+        for (pass, index) in [(0, 0), (0, 5), (2, 5), (1, 63), (7, 1000)] {
+            assert_eq!(
+                expected_word(Pattern::WalkingZeros, pass, index, 0),
+                !expected_word(Pattern::WalkingOnes, pass, index, 0)
+            );
+        }
+    }
+
+    #[test]
+    fn random_is_repeatable_and_changes_per_pass() {
+        let word = |pass, index| expected_word(Pattern::Random, pass, index, 0);
+        assert_eq!(word(3, 10), word(3, 10));
+        assert_ne!(word(0, 10), word(1, 10));
+        assert_ne!(word(0, 10), word(0, 11));
+    }
+
+    #[test]
+    fn address_in_address_holds_the_address() {
+        assert_eq!(
+            expected_word(Pattern::AddressInAddress, 4, 2, 0x1000),
+            0x1000
+        );
+    }
+
+    #[test]
+    fn verify_reports_a_flipped_bit() {
+        for pattern in Pattern::ALL {
+            let mut buffer = vec![0u64; 64];
+            assert!(write_pattern(&mut buffer, 100, pattern, 3, &running()));
+            let expected = buffer[7];
+            buffer[7] ^= 1 << 13;
+
+            let errors = no_errors();
+            assert!(verify_pattern(
+                &buffer,
+                100,
+                pattern,
+                3,
+                &errors,
+                &running()
+            ));
+            let (errors, count) = errors.into_inner().unwrap();
+            assert_eq!(count, 1, "{}", pattern.name());
+            let error = &errors[0];
+            assert_eq!(error.pattern, pattern);
+            assert_eq!(error.pass, 3);
+            assert_eq!(error.offset, 107 * size_of::<u64>());
+            assert_eq!(error.bit, 13);
+            assert_eq!(error.expected, expected);
+            assert_eq!(error.actual, expected ^ 1 << 13);
+        }
+    }
+
+    #[test]
+    fn reported_errors_are_capped_but_all_counted() {
+        let mut buffer = vec![0u64; 32];
+        write_pattern(&mut buffer, 0, Pattern::Random, 0, &running());
+        for word in &mut buffer {
+            *word = !*word;
+        }
+        let errors = no_errors();
+        verify_pattern(&buffer, 0, Pattern::Random, 0, &errors, &running());
+        let (errors, count) = errors.into_inner().unwrap();
+        assert_eq!(count, 32 * 64);
+        assert_eq!(errors.len(), MAX_REPORTED_ERRORS);
+    }
+
+    #[test]
+    fn stopped_writes_leave_the_buffer_alone() {
+        let stop = running();
+        stop.stop_signal.store(true, Ordering::Relaxed);
+        let mut buffer = vec![0u64; 16];
+        assert!(!write_pattern(
+            &mut buffer,
+            0,
+            Pattern::WalkingOnes,
+            0,
+            &stop
+        ));
+        assert!(buffer.iter().all(|&word| word == 0));
+    }
+
+    #[test]
+    fn run_without_a_complete_pass_is_inconclusive() {
+        let mut buffer = vec![0u64; 1024];
+        let report = memtest_blocking(
+            &mut buffer,
+            2,
+            Duration::ZERO,
+            Arc::new(AtomicBool::new(false)),
+        );
+        assert_eq!(report.passes, 0);
+        assert!(!report.passed());
+        assert!(report.inconclusive());
+    }
+
+    #[test]
+    fn clean_run_passes() {
+        let mut buffer = vec![0u64; 1024];
+        let report = memtest_blocking(
+            &mut buffer,
+            2,
+            Duration::from_millis(50),
+            Arc::new(AtomicBool::new(false)),
+        );
+        assert!(report.passes > 0);
+        assert_eq!(report.bytes_tested, 1024 * size_of::<u64>());
+        assert!(report.passed());
+    }
+}
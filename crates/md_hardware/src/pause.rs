@@ -0,0 +1,76 @@
+use std::{
+    sync::{
+        Arc, Condvar, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+/// How often parked workers look at the stop signal.
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Default)]
+struct PauseState {
+    paused_at: Option<Instant>,
+    /// Time spent in completed pauses.
+    paused_total: Duration,
+}
+
+/// Pauses and resumes the workers of a run. Paused workers sleep on a condition
+/// variable instead of spinning, and paused time is left out of the run's elapsed time.
+#[derive(Clone, Default)]
+pub struct PauseControl {
+    state: Arc<(Mutex<PauseState>, Condvar)>,
+}
+
+impl PauseControl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Workers park after finishing their current batch.
+    pub fn pause(&self) {
+        let mut state = self.state.0.lock().unwrap();
+        if state.paused_at.is_none() {
+            state.paused_at = Some(Instant::now());
+        }
+    }
+
+    pub fn resume(&self) {
+        let mut state = self.state.0.lock().unwrap();
+        if let Some(paused_at) = state.paused_at.take() {
+            state.paused_total += paused_at.elapsed();
+            self.state.1.notify_all();
+        }
+    }
+
+    /// Pauses a running test or resumes a paused one. Returns true if now paused.
+    pub fn toggle(&self) -> bool {
+        if self.is_paused() {
+            self.resume();
+            false
+        } else {
+            self.pause();
+            true
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.state.0.lock().unwrap().paused_at.is_some()
+    }
+
+    /// Total time spent paused so far, including a pause still in progress.
+    pub fn paused_for(&self) -> Duration {
+        let state = self.state.0.lock().unwrap();
+        state.paused_total + state.paused_at.map_or(Duration::ZERO, |at| at.elapsed())
+    }
+
+    /// Blocks the calling worker while paused. Returns early once `stop_signal` is set.
+    pub(crate) fn wait_while_paused(&self, stop_signal: &AtomicBool) {
+        let (lock, condvar) = &*self.state;
+        let mut state = lock.lock().unwrap();
+        while state.paused_at.is_some() && !stop_signal.load(Ordering::Relaxed) {
+            state = condvar.wait_timeout(state, STOP_POLL_INTERVAL).unwrap().0;
+        }
+    }
+}
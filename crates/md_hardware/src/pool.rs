@@ -0,0 +1,182 @@
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, Mutex, mpsc},
+    thread::{self, JoinHandle},
+};
+
+use tokio::sync::oneshot;
+
+use crate::affinity::{pin_current_thread, unpin_current_thread};
+
+/// OS scheduling priority for stress workers, from least to most favoured.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum WorkerPriority {
+    /// Yield to everything else, useful when the machine is still in use.
+    Low,
+    #[default]
+    Normal,
+    /// Ask the OS to favour stress workers. Usually needs elevated privileges and
+    /// stays at normal priority otherwise, as each worker's result records.
+    High,
+}
+
+impl WorkerPriority {
+    /// Applies the priority to the calling thread. Returns false if the OS refused.
+    fn apply(self) -> bool {
+        #[cfg(target_os = "linux")]
+        {
+            let nice = match self {
+                WorkerPriority::Low => 10,
+                WorkerPriority::Normal => 0,
+                WorkerPriority::High => -10,
+            };
+            // On Linux nice values are per thread when addressed by thread id.
+            let tid = unsafe { libc::gettid() } as libc::id_t;
+            unsafe { libc::setpriority(libc::PRIO_PROCESS, tid, nice) == 0 }
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            self == WorkerPriority::Normal
+        }
+    }
+}
+
+type Job = Box<dyn FnOnce() + Send>;
+
+struct PoolThread {
+    sender: mpsc::Sender<Job>,
+    handle: JoinHandle<()>,
+    /// Priority the thread currently runs at, updated by its jobs.
+    priority: Arc<Mutex<WorkerPriority>>,
+}
+
+impl PoolThread {
+    /// Spawns worker thread `index`. It starts at the caller's priority, assumed
+    /// to be normal.
+    fn spawn(index: usize) -> Self {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let handle = thread::Builder::new()
+            .name(format!("md-stress-{}", index))
+            .spawn(move || {
+                for job in receiver {
+                    job();
+                }
+            })
+            .expect("failed to spawn stress worker thread");
+        PoolThread {
+            sender,
+            handle,
+            priority: Arc::new(Mutex::new(WorkerPriority::Normal)),
+        }
+    }
+
+    /// Lets the thread finish its current job, then joins it.
+    fn stop(self) {
+        drop(self.sender);
+        let _ = self.handle.join();
+    }
+}
+
+/// Long-lived threads reserved for stress workers.
+///
+/// Unlike tokio's blocking pool, these threads run nothing but stress jobs, keep a
+/// stable identity per worker index and are reused across runs, so neither thread
+/// start-up nor the app's own blocking I/O shows up in the scores.
+pub struct StressPool {
+    threads: Vec<PoolThread>,
+}
+
+impl StressPool {
+    /// Creates an empty pool. Threads are spawned by `start`.
+    pub fn new() -> Self {
+        StressPool {
+            threads: Vec::new(),
+        }
+    }
+
+    /// Makes sure at least `threads` worker threads are running.
+    pub fn start(&mut self, threads: usize) {
+        while self.threads.len() < threads {
+            self.threads.push(PoolThread::spawn(self.threads.len()));
+        }
+    }
+
+    /// Lets every thread finish its current job, then joins them all.
+    pub fn stop(&mut self) {
+        for thread in self.threads.drain(..) {
+            thread.stop();
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        !self.threads.is_empty()
+    }
+
+    pub fn size(&self) -> usize {
+        self.threads.len()
+    }
+
+    /// Runs `job` on worker thread `index`, starting threads as needed. The thread is
+    /// pinned to `cpu` (or unpinned when `None`) and set to `priority` first. The job
+    /// gets the CPU it was pinned to and the priority it runs at, which stays lower
+    /// than asked if the OS refused. The receiver yields the job's return value, or
+    /// an error if it panicked.
+    pub fn run<T, F>(
+        &mut self,
+        index: usize,
+        cpu: Option<usize>,
+        priority: WorkerPriority,
+        job: F,
+    ) -> oneshot::Receiver<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(Option<usize>, WorkerPriority) -> T + Send + 'static,
+    {
+        self.start(index + 1);
+        // Raising the priority again usually needs privileges, so a thread an
+        // earlier run left less favoured is replaced by a fresh one
+        if priority > *self.threads[index].priority.lock().unwrap() {
+            let thread = std::mem::replace(&mut self.threads[index], PoolThread::spawn(index));
+            thread.stop();
+        }
+        let thread_priority = Arc::clone(&self.threads[index].priority);
+        let (sender, receiver) = oneshot::channel();
+        let job: Job = Box::new(move || {
+            let priority = {
+                let mut current = thread_priority.lock().unwrap();
+                if priority.apply() {
+                    *current = priority;
+                }
+                *current
+            };
+            let pinned_cpu = match cpu {
+                Some(cpu) => Some(cpu).filter(|&cpu| pin_current_thread(cpu)),
+                None => {
+                    unpin_current_thread();
+                    None
+                }
+            };
+            // Keep the thread alive for later jobs; the dropped sender reports the panic.
+            if let Ok(value) = panic::catch_unwind(AssertUnwindSafe(|| job(pinned_cpu, priority))) {
+                let _ = sender.send(value);
+            }
+        });
+        self.threads[index]
+            .sender
+            .send(job)
+            .expect("stress worker thread exited");
+        receiver
+    }
+}
+
+impl Default for StressPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for StressPool {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
@@ -0,0 +1,447 @@
+use std::time::Duration;
+
+/// Longest time `parse_time` accepts and a parsed profile may last. Far beyond any
+/// test, but small enough to add to an `Instant` without overflowing.
+pub const MAX_TIME: Duration = Duration::from_secs(10 * 365 * 24 * 3600);
+/// Most points `LoadProfile::points` returns, however small the step.
+const MAX_POINTS: u32 = 10_000;
+
+/// How the target load moves within one segment. Loads are CPU usage in percent.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Shape {
+    Hold(f32),
+    /// Linear change from one load to another over the segment.
+    Ramp {
+        from: f32,
+        to: f32,
+    },
+    /// Staircase from one load to another in `steps` equal steps.
+    Step {
+        from: f32,
+        to: f32,
+        steps: u32,
+    },
+    /// Alternates between `high` for `on` and `low` for `off`.
+    Square {
+        high: f32,
+        low: f32,
+        on: Duration,
+        off: Duration,
+    },
+    /// Oscillates between `min` and `max`, starting at the midpoint and rising.
+    Sine {
+        min: f32,
+        max: f32,
+        period: Duration,
+    },
+}
+
+impl Shape {
+    /// Target load at `t` into a segment lasting `duration`.
+    fn load_at(&self, t: Duration, duration: Duration) -> f32 {
+        let progress = if duration.is_zero() {
+            1.0
+        } else {
+            (t.as_secs_f32() / duration.as_secs_f32()).min(1.0)
+        };
+        match *self {
+            Shape::Hold(load) => load,
+            Shape::Ramp { from, to } => from + (to - from) * progress,
+            Shape::Step { from, to, steps } => {
+                let steps = steps.max(1);
+                let step = ((progress * steps as f32) as u32).min(steps - 1);
+                if steps == 1 {
+                    to
+                } else {
+                    from + (to - from) * step as f32 / (steps - 1) as f32
+                }
+            }
+            Shape::Square { high, low, on, off } => {
+                let cycle = (on + off).as_secs_f32();
+                if cycle <= 0.0 || t.as_secs_f32() % cycle < on.as_secs_f32() {
+                    high
+                } else {
+                    low
+                }
+            }
+            Shape::Sine { min, max, period } => {
+                let phase = if period.is_zero() {
+                    0.0
+                } else {
+                    t.as_secs_f32() / period.as_secs_f32() * std::f32::consts::TAU
+                };
+                min + (max - min) * (1.0 + phase.sin()) / 2.0
+            }
+        }
+    }
+}
+
+/// One part of a load profile.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Segment {
+    pub shape: Shape,
+    pub duration: Duration,
+    /// Workers running during the segment, or `None` for all of them.
+    pub threads: Option<usize>,
+}
+
+/// Where a profile is at a given time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProfilePoint {
+    /// Target CPU usage in percent.
+    pub load: f32,
+    pub threads: Option<usize>,
+}
+
+/// A timeline of target loads and worker counts, played back by
+/// `CpuExplosion::stress_test_cpu` instead of running flat out.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LoadProfile {
+    pub segments: Vec<Segment>,
+}
+
+impl LoadProfile {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a segment. Builder-style, e.g.
+    /// `LoadProfile::new().then(Shape::Ramp { from: 0.0, to: 100.0 }, Duration::from_secs(60), None)`.
+    pub fn then(mut self, shape: Shape, duration: Duration, threads: Option<usize>) -> Self {
+        self.segments.push(Segment {
+            shape,
+            duration,
+            threads,
+        });
+        self
+    }
+
+    /// Parses segments separated by ';'. Each is one of
+    ///
+    /// - `hold <load> <time>`
+    /// - `ramp <from> <to> <time>`
+    /// - `step <from> <to> <steps> <time>`
+    /// - `square <high> <low> <on> <off> <time>`
+    /// - `sine <min> <max> <period> <time>`
+    ///
+    /// optionally followed by `@<threads>`. Times take an `s`, `m` or `h` suffix and
+    /// default to seconds, e.g. "ramp 0 100 60s; hold 100 5m; square 100 0 10s 10s 2m @4".
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut profile = LoadProfile::new();
+        for part in spec.split(';').map(str::trim).filter(|p| !p.is_empty()) {
+            let mut words: Vec<&str> = part.split_whitespace().collect();
+            let threads = match words.last().and_then(|w| w.strip_prefix('@')) {
+                Some(threads) => {
+                    let threads = threads
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|&threads| threads > 0)
+                        .ok_or(format!("Invalid thread count in '{}'", part))?;
+                    words.pop();
+                    Some(threads)
+                }
+                None => None,
+            };
+
+            let load = |i: usize| -> Result<f32, String> {
+                let word = words.get(i).ok_or(format!("Missing value in '{}'", part))?;
+                match word.trim_end_matches('%').parse::<f32>() {
+                    Ok(load) if (0.0..=100.0).contains(&load) => Ok(load),
+                    _ => Err(format!("Invalid load '{}' in '{}'", word, part)),
+                }
+            };
+            let time = |i: usize| -> Result<Duration, String> {
+                let word = words.get(i).ok_or(format!("Missing time in '{}'", part))?;
+                parse_time(word).ok_or(format!("Invalid time '{}' in '{}'", word, part))
+            };
+            let Some(&kind) = words.first() else {
+                return Err(format!("Missing segment type in '{}'", part));
+            };
+            let (shape, arguments) = match kind {
+                "hold" => (Shape::Hold(load(1)?), 2),
+                "ramp" => (
+                    Shape::Ramp {
+                        from: load(1)?,
+                        to: load(2)?,
+                    },
+                    3,
+                ),
+                "step" => {
+                    let steps = words
+                        .get(3)
+                        .and_then(|w| w.parse::<u32>().ok())
+                        .filter(|&steps| steps > 0)
+                        .ok_or(format!("Invalid step count in '{}'", part))?;
+                    (
+                        Shape::Step {
+                            from: load(1)?,
+                            to: load(2)?,
+                            steps,
+                        },
+                        4,
+                    )
+                }
+                "square" => (
+                    Shape::Square {
+                        high: load(1)?,
+                        low: load(2)?,
+                        on: time(3)?,
+                        off: time(4)?,
+                    },
+                    5,
+                ),
+                "sine" => (
+                    Shape::Sine {
+                        min: load(1)?,
+                        max: load(2)?,
+                        period: time(3)?,
+                    },
+                    4,
+                ),
+                other => return Err(format!("Unknown segment '{}'", other)),
+            };
+            if words.len() != arguments + 1 {
+                return Err(format!("Expected {} values in '{}'", arguments, part));
+            }
+            profile = profile.then(shape, time(arguments)?, threads);
+        }
+
+        if profile.segments.is_empty() {
+            return Err("Empty load profile".to_string());
+        }
+        let total = profile
+            .segments
+            .iter()
+            .try_fold(Duration::ZERO, |total, s| total.checked_add(s.duration));
+        match total {
+            Some(Duration::ZERO) => return Err("Load profile has no length".to_string()),
+            Some(total) if total <= MAX_TIME => {}
+            _ => return Err("Load profile is too long".to_string()),
+        }
+        Ok(profile)
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.segments
+            .iter()
+            .fold(Duration::ZERO, |total, s| total.saturating_add(s.duration))
+    }
+
+    /// Most workers any segment explicitly asks for.
+    pub fn max_threads(&self) -> Option<usize> {
+        self.segments.iter().filter_map(|s| s.threads).max()
+    }
+
+    /// Target at `t` since the start, or `None` once the profile is over.
+    pub fn at(&self, t: Duration) -> Option<ProfilePoint> {
+        let mut start = Duration::ZERO;
+        for segment in &self.segments {
+            if t < start.saturating_add(segment.duration) {
+                return Some(ProfilePoint {
+                    load: segment.shape.load_at(t - start, segment.duration),
+                    threads: segment.threads,
+                });
+            }
+            start = start.saturating_add(segment.duration);
+        }
+        None
+    }
+
+    /// (seconds, load) pairs tracing the profile, one every `step`, for drawing it.
+    /// The step is widened where needed to stay within `MAX_POINTS`.
+    pub fn points(&self, step: Duration) -> Vec<(f64, f64)> {
+        let total = self.duration();
+        let step = step.max(total / MAX_POINTS).max(Duration::from_millis(1));
+        let mut points = Vec::new();
+        let mut t = Duration::ZERO;
+        while t < total {
+            if let Some(point) = self.at(t) {
+                points.push((t.as_secs_f64(), point.load as f64));
+            }
+            t += step;
+        }
+        if let Some(last) = self.segments.last() {
+            let end = last.shape.load_at(last.duration, last.duration);
+            points.push((total.as_secs_f64(), end as f64));
+        }
+        points
+    }
+}
+
+/// Parses "90", "90s", "5m" or "1h". Negative times and times above `MAX_TIME` are
+/// rejected.
+pub fn parse_time(word: &str) -> Option<Duration> {
+    let (number, scale) = match word.char_indices().last()? {
+        (i, 's') => (&word[..i], 1.0),
+        (i, 'm') => (&word[..i], 60.0),
+        (i, 'h') => (&word[..i], 3600.0),
+        _ => (word, 1.0),
+    };
+    let secs = number.parse::<f64>().ok()? * scale;
+    Duration::try_from_secs_f64(secs)
+        .ok()
+        .filter(|&time| time <= MAX_TIME)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn parse_time_accepts_suffixes() {
+        assert_eq!(parse_time("90"), Some(secs(90)));
+        assert_eq!(parse_time("90s"), Some(secs(90)));
+        assert_eq!(parse_time("5m"), Some(secs(300)));
+        assert_eq!(parse_time("1h"), Some(secs(3600)));
+        assert_eq!(parse_time("1.5s"), Some(Duration::from_millis(1500)));
+        assert_eq!(parse_time("0"), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn parse_time_rejects_garbage() {
+        for word in ["", "s", "abc", "5x", "m5", "1 h", "NaN", "inf", "infs"] {
+            assert_eq!(parse_time(word), None, "{:?}", word);
+        }
+    }
+
+    #[test]
+    fn parse_time_rejects_negative_and_huge_values() {
+        assert_eq!(parse_time("-1"), None);
+        assert_eq!(parse_time("-0.5m"), None);
+        assert_eq!(parse_time("1e30s"), None);
+        assert_eq!(parse_time("1e20"), None);
+        assert_eq!(parse_time("1e300h"), None);
+        assert!(parse_time(&format!("{}", MAX_TIME.as_secs())).is_some());
+        assert_eq!(parse_time(&format!("{}", MAX_TIME.as_secs() + 1)), None);
+    }
+
+    #[test]
+    fn parse_reads_every_segment_type() {
+        let profile =
+            LoadProfile::parse("hold 50 10s; ramp 0 100 1m; step 0 100 5 50; square 100 0 1 2 30 @2; sine 10 90 20s 1h")
+                .unwrap();
+        assert_eq!(profile.segments.len(), 5);
+        assert_eq!(profile.segments[0].shape, Shape::Hold(50.0));
+        assert_eq!(
+            profile.segments[1].shape,
+            Shape::Ramp {
+                from: 0.0,
+                to: 100.0
+            }
+        );
+        assert_eq!(
+            profile.segments[2].shape,
+            Shape::Step {
+                from: 0.0,
+                to: 100.0,
+                steps: 5
+            }
+        );
+        assert_eq!(profile.segments[3].threads, Some(2));
+        assert_eq!(profile.segments[4].duration, secs(3600));
+        assert_eq!(profile.duration(), secs(10 + 60 + 50 + 30 + 3600));
+        assert_eq!(profile.max_threads(), Some(2));
+    }
+
+    #[test]
+    fn parse_rejects_garbage() {
+        for spec in [
+            "",
+            " ; ",
+            "hold",
+            "hold 50",
+            "hold 50 10s extra",
+            "hold abc 10s",
+            "hold 50 abc",
+            "spin 50 10s",
+            "step 0 100 0 10s",
+            "hold 50 10s @x",
+            "hold 50 10s @0",
+            "hold 50 10s @-1",
+            "ramp 0 100",
+        ] {
+            assert!(LoadProfile::parse(spec).is_err(), "{:?}", spec);
+        }
+    }
+
+    #[test]
+    fn parse_rejects_negative_and_out_of_range_values() {
+        assert!(LoadProfile::parse("hold -5 10s").is_err());
+        assert!(LoadProfile::parse("hold 101 10s").is_err());
+        assert!(LoadProfile::parse("hold 50 -10s").is_err());
+        assert!(LoadProfile::parse("step 0 100 -2 10s").is_err());
+    }
+
+    #[test]
+    fn parse_rejects_huge_times() {
+        assert!(LoadProfile::parse("hold 50 1e20").is_err());
+        assert!(LoadProfile::parse("square 100 0 1e30s 1s 10s").is_err());
+        // Every segment fits, but together they last too long
+        let half = MAX_TIME.as_secs() / 2 + 1;
+        assert!(LoadProfile::parse(&format!("hold 50 {}; hold 50 {}", half, half)).is_err());
+    }
+
+    #[test]
+    fn at_switches_segments_at_their_boundaries() {
+        let profile = LoadProfile::parse("hold 20 10s; hold 80 5s @3").unwrap();
+        assert_eq!(profile.at(Duration::ZERO).unwrap().load, 20.0);
+        assert_eq!(profile.at(Duration::from_millis(9999)).unwrap().load, 20.0);
+        let second = profile.at(secs(10)).unwrap();
+        assert_eq!((second.load, second.threads), (80.0, Some(3)));
+        assert_eq!(profile.at(Duration::from_millis(14999)).unwrap().load, 80.0);
+        assert_eq!(profile.at(secs(15)), None);
+        assert_eq!(profile.at(Duration::MAX), None);
+    }
+
+    #[test]
+    fn at_follows_ramps_and_steps() {
+        let profile = LoadProfile::parse("ramp 0 100 10s; step 0 100 3 30s").unwrap();
+        assert_eq!(profile.at(Duration::ZERO).unwrap().load, 0.0);
+        assert_eq!(profile.at(secs(5)).unwrap().load, 50.0);
+        assert_eq!(profile.at(secs(10)).unwrap().load, 0.0);
+        assert_eq!(profile.at(secs(20)).unwrap().load, 50.0);
+        assert_eq!(
+            profile.at(Duration::from_millis(39999)).unwrap().load,
+            100.0
+        );
+    }
+
+    #[test]
+    fn at_alternates_square_waves() {
+        let profile = LoadProfile::parse("square 100 0 2s 3s 20s").unwrap();
+        assert_eq!(profile.at(Duration::ZERO).unwrap().load, 100.0);
+        assert_eq!(profile.at(secs(2)).unwrap().load, 0.0);
+        assert_eq!(profile.at(secs(5)).unwrap().load, 100.0);
+    }
+
+    #[test]
+    fn parse_rejects_profiles_without_length() {
+        assert!(LoadProfile::parse("hold 50 0").is_err());
+        assert!(LoadProfile::parse("hold 50 0s; ramp 0 100 0m").is_err());
+    }
+
+    #[test]
+    fn points_trace_the_profile() {
+        let profile = LoadProfile::parse("ramp 0 100 10s").unwrap();
+        let points = profile.points(secs(5));
+        assert_eq!(points, [(0.0, 0.0), (5.0, 50.0), (10.0, 100.0)]);
+    }
+
+    #[test]
+    fn points_are_bounded_for_tiny_steps() {
+        let profile = LoadProfile::parse("hold 50 1000h").unwrap();
+        let points = profile.points(Duration::ZERO);
+        assert!(points.len() <= MAX_POINTS as usize + 1, "{}", points.len());
+        assert_eq!(points.last(), Some(&(3_600_000.0, 50.0)));
+    }
+
+    #[test]
+    fn zero_length_segments_are_skipped() {
+        let profile = LoadProfile::parse("hold 10 0; hold 90 5").unwrap();
+        assert_eq!(profile.at(Duration::ZERO).unwrap().load, 90.0);
+    }
+}
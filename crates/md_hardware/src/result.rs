@@ -0,0 +1,83 @@
+use std::time::{Duration, SystemTime};
+
+use crate::{frequency::ThrottleReport, pool::WorkerPriority, thermal::ThermalAbort};
+
+/// A wrong result reported by a self-checking workload.
+#[derive(Clone, Debug)]
+pub struct ComputationError {
+    pub thread_index: usize,
+    /// Logical CPU the worker was running on when the error was found, if known.
+    pub core: Option<usize>,
+    /// Time since the start of the run.
+    pub at: Duration,
+    pub message: String,
+}
+
+/// Work done by a single worker thread.
+#[derive(Clone, Debug)]
+pub struct WorkerResult {
+    pub thread_index: usize,
+    /// Logical CPU the worker was pinned to, if pinning was requested and succeeded.
+    pub pinned_cpu: Option<usize>,
+    /// Logical CPU the worker spent most of its batches on, if the platform reports it.
+    pub core: Option<usize>,
+    /// Number of distinct logical CPUs the worker was seen on.
+    pub cores_seen: usize,
+    /// Scheduling priority the worker ran at. Differs from the configured one when
+    /// the OS refused to change it.
+    pub priority: WorkerPriority,
+    pub score: u64,
+    pub rate: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct StressResult {
+    pub workload: String,
+    /// Sum of all worker scores.
+    pub score: u64,
+    /// Workload-specific rate over the whole run, e.g. GFLOPS.
+    pub rate: f64,
+    pub rate_unit: &'static str,
+    /// One entry per worker thread, ordered by thread index.
+    pub workers: Vec<WorkerResult>,
+    pub started_at: SystemTime,
+    pub finished_at: SystemTime,
+    pub elapsed: Duration,
+    pub errors: Vec<ComputationError>,
+    /// Set when the run was stopped early by its thermal limit.
+    pub thermal_abort: Option<ThermalAbort>,
+    /// Hottest sensor reading seen during the run, in °C.
+    pub peak_temperature: Option<f32>,
+    pub throttling: ThrottleReport,
+}
+
+impl StressResult {
+    /// A run fails as soon as any worker computed a wrong result.
+    pub fn failed(&self) -> bool {
+        !self.errors.is_empty()
+    }
+
+    /// Short description of how the run ended, for summaries and exports.
+    pub fn outcome(&self) -> &'static str {
+        if self.thermal_abort.is_some() {
+            "aborted: thermal limit"
+        } else if self.failed() {
+            "failed"
+        } else {
+            "passed"
+        }
+    }
+
+    /// Mean score per worker.
+    pub fn mean_worker_score(&self) -> f64 {
+        if self.workers.is_empty() {
+            return 0.0;
+        }
+        self.score as f64 / self.workers.len() as f64
+    }
+
+    /// The worker with the lowest score, the first place to look for a weak core.
+    pub fn slowest_worker(&self) -> Option<&WorkerResult> {
+        self.workers.iter().min_by_key(|w| w.score)
+    }
+}
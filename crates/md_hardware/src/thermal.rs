@@ -0,0 +1,229 @@
+use std::time::Duration;
+
+use crate::{Temperature, profile::parse_time};
+
+/// When to abort a run because the machine is overheating. Temperatures are in °C
+/// and apply to every sensor.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ThermalLimit {
+    /// Abort as soon as any sensor reaches this.
+    pub critical: f32,
+    /// Abort once a sensor stays above the temperature for the duration.
+    pub sustained: Option<(f32, Duration)>,
+}
+
+impl ThermalLimit {
+    pub fn new(critical: f32) -> Self {
+        ThermalLimit {
+            critical,
+            sustained: None,
+        }
+    }
+
+    pub fn with_sustained(mut self, above: f32, duration: Duration) -> Self {
+        self.sustained = Some((above, duration));
+        self
+    }
+
+    /// Parses "95" or "95, 85 for 30s": abort at 95 °C, or after 30 seconds above 85 °C.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let temperature = |word: &str| {
+            word.trim()
+                .trim_end_matches("°C")
+                .trim_end_matches('C')
+                .parse::<f32>()
+                .ok()
+                .filter(|t| t.is_finite())
+                .ok_or(format!("Invalid temperature '{}'", word.trim()))
+        };
+        let (critical, sustained) = match spec.split_once(',') {
+            Some((critical, sustained)) => (critical, Some(sustained)),
+            None => (spec, None),
+        };
+        let mut limit = ThermalLimit::new(temperature(critical)?);
+        if let Some(sustained) = sustained {
+            let (above, duration) = sustained.split_once(" for ").ok_or(format!(
+                "Expected '<temperature> for <time>' in '{}'",
+                sustained.trim()
+            ))?;
+            let duration =
+                parse_time(duration.trim()).ok_or(format!("Invalid time '{}'", duration.trim()))?;
+            limit = limit.with_sustained(temperature(above)?, duration);
+        }
+        Ok(limit)
+    }
+}
+
+/// Why a run was stopped early by its `ThermalLimit`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ThermalAbort {
+    /// Label of the sensor that tripped the limit.
+    pub sensor: String,
+    pub temperature: f32,
+    /// Time since the start of the run.
+    pub at: Duration,
+    /// Human-readable rule that was broken, e.g. "above 85°C for 30s".
+    pub rule: String,
+}
+
+/// Checks readings against a `ThermalLimit`, remembering how long sensors have
+/// been running hot.
+pub(crate) struct ThermalWatch {
+    limit: ThermalLimit,
+    /// When the hottest sensor went above the sustained limit, if it still is.
+    hot_since: Option<Duration>,
+}
+
+impl ThermalWatch {
+    pub fn new(limit: ThermalLimit) -> Self {
+        ThermalWatch {
+            limit,
+            hot_since: None,
+        }
+    }
+
+    /// Returns the breach, if `temperatures` read at `at` break the limit.
+    pub fn check(&mut self, temperatures: &[Temperature], at: Duration) -> Option<ThermalAbort> {
+        let (sensor, temperature) = temperatures
+            .iter()
+            .filter_map(|t| t.current.map(|current| (&t.label, current)))
+            .max_by(|a, b| a.1.total_cmp(&b.1))?;
+        let abort = |rule: String| ThermalAbort {
+            sensor: sensor.clone(),
+            temperature,
+            at,
+            rule,
+        };
+
+        if temperature >= self.limit.critical {
+            return Some(abort(format!("reached {:.0}°C", self.limit.critical)));
+        }
+        if let Some((above, duration)) = self.limit.sustained {
+            if temperature <= above {
+                self.hot_since = None;
+            } else {
+                // Run time can step back slightly while paused, so never go below zero
+                let hot_for = at.saturating_sub(*self.hot_since.get_or_insert(at));
+                if hot_for >= duration {
+                    return Some(abort(format!(
+                        "above {:.0}°C for {}s",
+                        above,
+                        duration.as_secs()
+                    )));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Current reading of the hottest sensor, in °C.
+pub fn hottest_temperature(temperatures: &[Temperature]) -> Option<f32> {
+    temperatures
+        .iter()
+        .filter_map(|t| t.current)
+        .max_by(|a, b| a.total_cmp(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sensor(label: &str, current: f32) -> Temperature {
+        Temperature {
+            label: label.to_string(),
+            current: Some(current),
+            max: None,
+            critical: None,
+        }
+    }
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn parse_critical_only() {
+        assert_eq!(ThermalLimit::parse("95"), Ok(ThermalLimit::new(95.0)));
+        assert_eq!(ThermalLimit::parse(" 95°C "), Ok(ThermalLimit::new(95.0)));
+        assert_eq!(ThermalLimit::parse("95C"), Ok(ThermalLimit::new(95.0)));
+    }
+
+    #[test]
+    fn parse_critical_and_sustained() {
+        assert_eq!(
+            ThermalLimit::parse("95, 85 for 30s"),
+            Ok(ThermalLimit::new(95.0).with_sustained(85.0, secs(30)))
+        );
+        assert_eq!(
+            ThermalLimit::parse("100,90°C for 2m"),
+            Ok(ThermalLimit::new(100.0).with_sustained(90.0, secs(120)))
+        );
+    }
+
+    #[test]
+    fn parse_rejects_bad_specs() {
+        for spec in [
+            "",
+            "hot",
+            "inf",
+            "95, 85",
+            "95, 85 for soon",
+            "95, warm for 30s",
+        ] {
+            assert!(ThermalLimit::parse(spec).is_err(), "accepted '{}'", spec);
+        }
+    }
+
+    #[test]
+    fn critical_trips_straight_away() {
+        let mut watch = ThermalWatch::new(ThermalLimit::new(95.0));
+        assert_eq!(watch.check(&[sensor("core", 94.0)], secs(1)), None);
+        let abort = watch
+            .check(&[sensor("core", 80.0), sensor("package", 96.0)], secs(2))
+            .unwrap();
+        assert_eq!(abort.sensor, "package");
+        assert_eq!(abort.temperature, 96.0);
+        assert_eq!(abort.at, secs(2));
+        assert_eq!(abort.rule, "reached 95°C");
+    }
+
+    #[test]
+    fn sustained_trips_after_the_duration() {
+        let mut watch = ThermalWatch::new(ThermalLimit::new(100.0).with_sustained(85.0, secs(30)));
+        assert_eq!(watch.check(&[sensor("core", 90.0)], secs(10)), None);
+        assert_eq!(watch.check(&[sensor("core", 90.0)], secs(39)), None);
+        let abort = watch.check(&[sensor("core", 90.0)], secs(40)).unwrap();
+        assert_eq!(abort.rule, "above 85°C for 30s");
+        assert_eq!(abort.at, secs(40));
+    }
+
+    #[test]
+    fn cooling_down_resets_the_timer() {
+        let mut watch = ThermalWatch::new(ThermalLimit::new(100.0).with_sustained(85.0, secs(30)));
+        assert_eq!(watch.check(&[sensor("core", 90.0)], secs(0)), None);
+        assert_eq!(watch.check(&[sensor("core", 85.0)], secs(20)), None);
+        assert_eq!(watch.check(&[sensor("core", 90.0)], secs(25)), None);
+        assert_eq!(watch.check(&[sensor("core", 90.0)], secs(50)), None);
+        assert!(watch.check(&[sensor("core", 90.0)], secs(55)).is_some());
+    }
+
+    #[test]
+    fn time_going_backwards_does_not_panic() {
+        let mut watch = ThermalWatch::new(ThermalLimit::new(100.0).with_sustained(85.0, secs(30)));
+        assert_eq!(watch.check(&[sensor("core", 90.0)], secs(10)), None);
+        let earlier = secs(10) - Duration::from_nanos(5);
+        assert_eq!(watch.check(&[sensor("core", 90.0)], earlier), None);
+    }
+
+    #[test]
+    fn sensors_without_a_reading_are_ignored() {
+        let mut watch = ThermalWatch::new(ThermalLimit::new(95.0));
+        let unread = Temperature {
+            current: None,
+            ..sensor("core", 0.0)
+        };
+        assert_eq!(watch.check(&[unread], secs(1)), None);
+        assert_eq!(watch.check(&[], secs(1)), None);
+    }
+}
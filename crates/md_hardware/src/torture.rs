@@ -0,0 +1,116 @@
+use std::hint::black_box;
+
+use crate::workload::Workload;
+
+/// Rounds per kernel run, about a millisecond on a current desktop core.
+const ROUNDS: u32 = 50000;
+
+/// Seeds and the results a correctly working CPU produces for them. The kernel only
+/// uses integer operations and correctly rounded IEEE 754 arithmetic, so every
+/// conforming machine computes exactly these values.
+const KNOWN_GOOD: [(u64, u64); 16] = [
+    (0x5eed000000000000, 0x50e8c7a93144e6bf),
+    (0x7ba8f4914f6cdd1d, 0xfb51a91d201f1120),
+    (0x1466e9229ed9ba3a, 0x6ab074ebcfde75c8),
+    (0x313cddb3ee469757, 0x26f8b421418e2d12),
+    (0xcbfad2453db37474, 0xe8ea9a80fd27484a),
+    (0xe4b0c6d68d205191, 0x2bcf1863cf074f9f),
+    (0x814ebb67dc8d2eae, 0x3dbea7c06ff08e71),
+    (0x5a04aff92bfa0bcb, 0xae25842ac6309b9b),
+    (0x74c2a48a7b66e8e8, 0xa1f1581a36b017d0),
+    (0x1198991bcad3c605, 0x01a12ec899f52094),
+    (0x2a568dad1a40a322, 0xa8d837c6eb8be84d),
+    (0xc4ec823e69ad803f, 0x046e23d9939f6aad),
+    (0xe1aa76cfb91a5d5c, 0xfaa7e9853b174432),
+    (0xba606b6108873a79, 0x1a554a70ce9753df),
+    (0x573e5ff257f41796, 0x25f1da638e6d8cef),
+    (0x71f45483a760f4b3, 0x3af0b2ddac46a072),
+];
+
+/// Mixed integer and floating-point kernel whose every result is checked against
+/// `KNOWN_GOOD`, in the spirit of Prime95's torture test. One work unit is one
+/// verified kernel run.
+pub struct Torture {
+    next: usize,
+}
+
+impl Torture {
+    pub fn new() -> Self {
+        Torture { next: 0 }
+    }
+}
+
+impl Default for Torture {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Workload for Torture {
+    fn init(&mut self, thread_index: usize) {
+        // Start threads on different seeds so they do not run in lockstep.
+        self.next = thread_index % KNOWN_GOOD.len();
+    }
+
+    fn run_batch(&mut self) -> Result<u64, String> {
+        let (seed, expected) = KNOWN_GOOD[self.next];
+        self.next = (self.next + 1) % KNOWN_GOOD.len();
+
+        let actual = torture_kernel(black_box(seed));
+        if actual != expected {
+            return Err(format!(
+                "seed {:#018x} gave {:#018x}, expected {:#018x}",
+                seed, actual, expected
+            ));
+        }
+        Ok(1)
+    }
+
+    fn rate_unit(&self) -> &'static str {
+        "checks/s"
+    }
+}
+
+fn torture_kernel(seed: u64) -> u64 {
+    let mut x = seed;
+    let mut f = (seed >> 11) as f64 / (1u64 << 53) as f64 + 1.0;
+    for _ in 0..ROUNDS {
+        x = x.wrapping_mul(0x9E3779B97F4A7C15).rotate_left(23) ^ (x >> 29);
+        f = (f * 1.5 + (x >> 11) as f64 / (1u64 << 53) as f64).sqrt();
+        f = f / 1.25 + 0.5;
+        x ^= f.to_bits();
+    }
+    x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kernel_reproduces_every_known_good_result() {
+        for (seed, expected) in KNOWN_GOOD {
+            assert_eq!(torture_kernel(seed), expected, "seed {:#018x}", seed);
+        }
+    }
+
+    #[test]
+    fn kernel_notices_a_single_flipped_bit() {
+        for (seed, expected) in KNOWN_GOOD {
+            for bit in [0, 31, 63] {
+                assert_ne!(torture_kernel(seed ^ (1 << bit)), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn batches_pass_through_every_seed() {
+        let mut torture = Torture::new();
+        torture.init(KNOWN_GOOD.len() + 3);
+        assert_eq!(torture.next, 3);
+        for _ in 0..KNOWN_GOOD.len() * 2 {
+            assert_eq!(torture.run_batch(), Ok(1));
+        }
+        assert_eq!(torture.next, 3);
+    }
+}
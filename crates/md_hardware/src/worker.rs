@@ -0,0 +1,141 @@
+use std::{
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{
+    CpuUsage,
+    affinity::current_cpu,
+    duty::{LoadControl, PERIOD, Throttle},
+    events::{EventSink, StressEvent},
+    frequency::ThrottleDetector,
+    pause::PauseControl,
+    pool::WorkerPriority,
+    result::{ComputationError, WorkerResult},
+    thermal::ThermalAbort,
+    workload::Workload,
+};
+
+/// State shared by the workers and the sampler of one run.
+pub(crate) struct RunState {
+    pub start_time: Instant,
+    pub duration: Duration,
+    pub stop_signal: Arc<AtomicBool>,
+    pub pause: PauseControl,
+    pub events: EventSink,
+    /// Work units completed so far, one counter per worker.
+    pub progress: Vec<AtomicU64>,
+    pub load: LoadControl,
+    /// CPU each worker is pinned to, ordered by thread index, or `None` when unpinned.
+    pub cpus: Option<Vec<usize>>,
+    pub throttling: Mutex<ThrottleDetector>,
+    /// Set by the sampler before it stops an overheating run.
+    pub thermal_abort: Mutex<Option<ThermalAbort>>,
+    /// Hottest sensor reading so far, in °C.
+    pub peak_temperature: Mutex<Option<f32>>,
+}
+
+impl RunState {
+    /// Time the run has been going, not counting pauses.
+    pub fn elapsed(&self) -> Duration {
+        self.start_time
+            .elapsed()
+            .saturating_sub(self.pause.paused_for())
+    }
+
+    /// Readings of the CPUs the active workers run on. Unpinned workers can be
+    /// anywhere, so the busiest CPUs stand in for them.
+    pub fn worker_cpus<'a>(&self, usage: &'a [CpuUsage]) -> Vec<&'a CpuUsage> {
+        let active = self.load.active_threads().min(self.progress.len());
+        match &self.cpus {
+            Some(cpus) => {
+                let mut cpus = cpus[..active].to_vec();
+                cpus.sort_unstable();
+                cpus.dedup();
+                cpus.iter().filter_map(|&cpu| usage.get(cpu)).collect()
+            }
+            None => {
+                let mut busiest: Vec<&CpuUsage> = usage.iter().collect();
+                busiest.sort_by(|a, b| b.usage.total_cmp(&a.usage));
+                busiest.truncate(active);
+                busiest
+            }
+        }
+    }
+
+    pub fn should_stop(&self) -> bool {
+        self.pause.wait_while_paused(&self.stop_signal);
+        if self.stop_signal.load(Ordering::Relaxed) {
+            return true;
+        }
+        if self.elapsed() >= self.duration {
+            self.stop_signal.store(true, Ordering::Relaxed);
+            return true;
+        }
+        false
+    }
+}
+
+pub(crate) fn run_workload_blocking(
+    mut workload: Box<dyn Workload>,
+    thread_index: usize,
+    pinned_cpu: Option<usize>,
+    priority: WorkerPriority,
+    run: Arc<RunState>,
+) -> (WorkerResult, Option<ComputationError>) {
+    let mut converted_score = 0;
+    let mut error = None;
+    // (logical cpu, batches seen on it)
+    let mut cores: Vec<(usize, u64)> = Vec::new();
+    let mut throttle = Throttle::new();
+    workload.init(thread_index);
+
+    while !run.should_stop() {
+        if !run.load.is_active(thread_index) {
+            std::thread::sleep(PERIOD);
+            continue;
+        }
+        if let Some(cpu) = current_cpu() {
+            match cores.iter_mut().find(|(c, _)| *c == cpu) {
+                Some(entry) => entry.1 += 1,
+                None => cores.push((cpu, 1)),
+            }
+        }
+
+        match workload.run_batch() {
+            Ok(units) => {
+                converted_score += units;
+                run.progress[thread_index].store(converted_score, Ordering::Relaxed);
+                throttle.after_batch(run.load.duty());
+            }
+            Err(message) => {
+                let e = ComputationError {
+                    thread_index,
+                    core: current_cpu(),
+                    at: run.elapsed(),
+                    message,
+                };
+                run.events.send(StressEvent::Error(e.clone()));
+                error = Some(e);
+                break;
+            }
+        }
+    }
+
+    let worker = WorkerResult {
+        thread_index,
+        pinned_cpu,
+        core: cores
+            .iter()
+            .max_by_key(|(_, count)| *count)
+            .map(|(cpu, _)| *cpu),
+        cores_seen: cores.len(),
+        priority,
+        score: converted_score,
+        rate: 0.0,
+    };
+    (worker, error)
+}
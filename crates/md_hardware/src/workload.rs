@@ -0,0 +1,157 @@
+use std::{hint::black_box, sync::Arc, time::Duration};
+
+use crate::{
+    fma::{FmaWorkload, Precision},
+    torture::Torture,
+};
+
+/// A unit of CPU work that `CpuExplosion` runs on every worker thread.
+///
+/// Each worker gets its own instance from the registry, calls `init` once and then
+/// keeps calling `run_batch` until the test is stopped or its time runs out. A batch
+/// should be short (around a millisecond) so workers react quickly to the stop signal.
+pub trait Workload: Send {
+    /// Prepares per-thread state. Called on the worker thread before the first batch.
+    fn init(&mut self, _thread_index: usize) {}
+
+    /// Runs one batch of work and returns the number of work units it completed.
+    ///
+    /// Workloads that verify their own results return an error describing the wrong
+    /// result instead. The worker stops and the whole run is marked as failed.
+    fn run_batch(&mut self) -> Result<u64, String>;
+
+    /// Label for the value returned by `rate`.
+    fn rate_unit(&self) -> &'static str {
+        "units/s"
+    }
+
+    /// Converts the work units completed over `elapsed` into the workload's own score.
+    fn rate(&self, units: u64, elapsed: Duration) -> f64 {
+        units as f64 / elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+pub type WorkloadFactory = Arc<dyn Fn() -> Box<dyn Workload> + Send + Sync>;
+
+/// Named workloads that `CpuExplosion` can dispatch to.
+#[derive(Clone)]
+pub struct WorkloadRegistry {
+    workloads: Vec<(String, WorkloadFactory)>,
+}
+
+impl WorkloadRegistry {
+    /// Creates a registry holding the built-in workloads.
+    pub fn new() -> Self {
+        let mut registry = Self::empty();
+        registry.register("fibonacci", || Box::new(Fibonacci::new()));
+        registry.register("fma-f32", || Box::new(FmaWorkload::new(Precision::F32)));
+        registry.register("fma-f64", || Box::new(FmaWorkload::new(Precision::F64)));
+        registry.register("torture", || Box::new(Torture::new()));
+        registry
+    }
+
+    /// Creates a registry without any workloads.
+    pub fn empty() -> Self {
+        Self {
+            workloads: Vec::new(),
+        }
+    }
+
+    /// Adds a workload, replacing any existing one with the same name.
+    pub fn register<F>(&mut self, name: impl Into<String>, factory: F)
+    where
+        F: Fn() -> Box<dyn Workload> + Send + Sync + 'static,
+    {
+        let name = name.into();
+        let factory: WorkloadFactory = Arc::new(factory);
+        match self.workloads.iter_mut().find(|(n, _)| *n == name) {
+            Some(entry) => entry.1 = factory,
+            None => self.workloads.push((name, factory)),
+        }
+    }
+
+    /// Names of all registered workloads, in registration order.
+    pub fn names(&self) -> Vec<&str> {
+        self.workloads
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.workloads.iter().any(|(n, _)| n == name)
+    }
+
+    /// Finds a workload by its full name or an unambiguous prefix, e.g. "fib".
+    pub fn resolve(&self, name: &str) -> Result<&str, String> {
+        if let Some((n, _)) = self.workloads.iter().find(|(n, _)| n == name) {
+            return Ok(n);
+        }
+        let matches: Vec<&str> = self
+            .names()
+            .into_iter()
+            .filter(|n| n.starts_with(name))
+            .collect();
+        match matches[..] {
+            [n] => Ok(n),
+            [] => Err(format!(
+                "Unknown workload '{}', expected one of: {}",
+                name,
+                self.names().join(", ")
+            )),
+            _ => Err(format!("Workload '{}' is ambiguous: {}", name, matches.join(", "))),
+        }
+    }
+
+    /// Builds a fresh instance of the named workload.
+    pub fn create(&self, name: &str) -> Option<Box<dyn Workload>> {
+        self.workloads
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, factory)| factory())
+    }
+}
+
+impl Default for WorkloadRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+const FIBONACCI_BATCH: u64 = 1000000;
+
+/// Scalar `u64` Fibonacci additions. One work unit is a million additions.
+pub struct Fibonacci {
+    a: u64,
+    b: u64,
+}
+
+impl Fibonacci {
+    pub fn new() -> Self {
+        Fibonacci { a: 0, b: 1 }
+    }
+}
+
+impl Default for Fibonacci {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Workload for Fibonacci {
+    fn run_batch(&mut self) -> Result<u64, String> {
+        for _ in 0..FIBONACCI_BATCH {
+            match self.a.checked_add(self.b) {
+                Some(n) => {
+                    self.a = self.b;
+                    self.b = black_box(n);
+                }
+                None => {
+                    self.a = 0;
+                    self.b = 1;
+                }
+            }
+        }
+        Ok(1)
+    }
+}
@@ -0,0 +1,96 @@
+use std::{path::PathBuf, time::Duration};
+
+use clap::{Args, Parser, Subcommand};
+
+use crate::{TimeUnit, theme::Theme};
+
+/// CPU and memory stress tester with live charts.
+#[derive(Parser)]
+#[command(version, about, args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    #[command(flatten)]
+    pub form: FormArgs,
+}
+
+/// Values filled into the input form when the TUI starts.
+#[derive(Args)]
+pub struct FormArgs {
+    /// Test duration, counted in --unit.
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    pub duration: Option<u64>,
+    /// Unit of --duration.
+    #[arg(long, value_enum, default_value_t = TimeUnit::Seconds)]
+    pub unit: TimeUnit,
+    /// Number of worker threads.
+    #[arg(long)]
+    pub cores: Option<usize>,
+    /// Workload name or an unambiguous prefix of one, e.g. "fib".
+    #[arg(long)]
+    pub workload: Option<String>,
+    /// Colours for a "dark" or "light" terminal.
+    #[arg(long, value_parser = Theme::parse, default_value = "dark")]
+    pub theme: Theme,
+    /// Start the test right away instead of showing the form, on every logical CPU
+    /// unless --cores is given.
+    #[arg(long, requires = "duration")]
+    pub start: bool,
+    /// Run the stages of a TOML test plan, see plans/qualification.toml.
+    #[arg(long, conflicts_with = "start")]
+    pub plan: Option<PathBuf>,
+    /// Run --workload on 1, 2, 4, ... up to every logical CPU, each for --duration.
+    #[arg(long, requires = "duration", conflicts_with_all = ["start", "plan"])]
+    pub sweep: bool,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Runs a stress test without the TUI, printing progress to stderr.
+    #[command(
+        after_help = "Exit codes: 0 passed, 1 wrong results, 2 could not run, \
+                            3 thermal limit reached, 4 score below --min-score"
+    )]
+    Run(RunArgs),
+}
+
+#[derive(Args)]
+pub struct RunArgs {
+    /// How long to run, e.g. 90, 90s, 5m or 1h. Not needed with --profile.
+    #[arg(long, value_parser = parse_duration, required_unless_present = "profile")]
+    pub duration: Option<Duration>,
+    /// Number of worker threads. Defaults to one per pinned CPU, or every logical CPU.
+    #[arg(long)]
+    pub cores: Option<usize>,
+    /// Workload name or an unambiguous prefix of one, e.g. "fib".
+    #[arg(long, default_value = "fibonacci")]
+    pub workload: String,
+    /// CPUs to pin workers to, e.g. "0,2,4-7" or "physical".
+    #[arg(long)]
+    pub affinity: Option<String>,
+    /// CPU usage in percent to hold instead of running flat out.
+    #[arg(long)]
+    pub target_load: Option<f32>,
+    /// Load profile, e.g. "ramp 0 100 60s; hold 100 5m".
+    #[arg(long)]
+    pub profile: Option<String>,
+    /// Abort when sensors get too hot, e.g. "95" or "95, 85 for 30s".
+    #[arg(long)]
+    pub thermal_limit: Option<String>,
+    /// Fail the run when its total score is lower than this.
+    #[arg(long)]
+    pub min_score: Option<u64>,
+    /// Where to write the JSON result; the time series goes next to it as CSV.
+    /// Defaults to a new file in the results directory.
+    #[arg(long)]
+    pub output: Option<PathBuf>,
+}
+
+fn parse_duration(arg: &str) -> Result<Duration, String> {
+    md_hardware::parse_time(arg)
+        .filter(|duration| !duration.is_zero())
+        .ok_or(format!(
+            "Invalid duration '{}', expected e.g. 90s, 5m or 1h",
+            arg
+        ))
+}
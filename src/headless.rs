@@ -0,0 +1,370 @@
+use std::{
+    path::Path,
+    process::ExitCode,
+    sync::atomic::Ordering,
+    time::{Duration, Instant},
+};
+
+use md_hardware::{
+    CpuAffinity, CpuExplosion, LoadProfile, StressConfig, StressEvent, StressResult, SystemUsage,
+    ThermalLimit, hottest_temperature,
+};
+
+use crate::{
+    cli::RunArgs,
+    results::{RESULTS_DIR, RunRecord, RunSample, RunSettings},
+};
+
+const EXIT_FAILED: u8 = 1;
+const EXIT_ERROR: u8 = 2;
+const EXIT_THERMAL_ABORT: u8 = 3;
+const EXIT_BELOW_THRESHOLD: u8 = 4;
+
+/// How often a progress line is printed.
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Runs a stress test without the TUI and saves its result. The exit code tells
+/// scripts how the run ended.
+pub async fn run(args: RunArgs) -> ExitCode {
+    match run_test(&args).await {
+        Ok(record) => {
+            let saved = match &args.output {
+                Some(path) => record.write(path).map(|_| path.clone()),
+                None => record.save(Path::new(RESULTS_DIR)),
+            };
+            match saved {
+                Ok(path) => eprintln!("Saved to {}", path.display()),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return ExitCode::from(EXIT_ERROR);
+                }
+            }
+            exit_code(&record, args.min_score)
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::from(EXIT_ERROR)
+        }
+    }
+}
+
+fn exit_code(record: &RunRecord, min_score: Option<u64>) -> ExitCode {
+    if record.thermal_abort.is_some() {
+        ExitCode::from(EXIT_THERMAL_ABORT)
+    } else if !record.errors.is_empty() {
+        ExitCode::from(EXIT_FAILED)
+    } else if let Some(min_score) = min_score
+        && record.score < min_score
+    {
+        eprintln!(
+            "Score {} is below the minimum of {}",
+            record.score, min_score
+        );
+        ExitCode::from(EXIT_BELOW_THRESHOLD)
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Builds the stress test configuration from the command line.
+fn config(
+    args: &RunArgs,
+    stress_test: &CpuExplosion,
+    logical_cores: usize,
+) -> Result<StressConfig, String> {
+    if args.profile.is_some() && args.target_load.is_some() {
+        return Err(
+            "--profile and --target-load cannot be combined, the profile sets the load".to_string(),
+        );
+    }
+    let workload = stress_test.workloads().resolve(&args.workload)?;
+    let affinity = CpuAffinity::parse(args.affinity.as_deref().unwrap_or(""))?;
+    let threads = match args.cores {
+        Some(cores) if cores == 0 || cores > logical_cores => {
+            return Err(format!(
+                "Cores must be between 1 and {}, got {}",
+                logical_cores, cores
+            ));
+        }
+        Some(cores) => cores,
+        None => affinity
+            .resolve(logical_cores)?
+            .map_or(logical_cores, |cpus| cpus.len()),
+    };
+    let duration = args
+        .duration
+        .map_or(0, |duration| duration.as_secs_f64().ceil() as u64);
+
+    let mut config = StressConfig::new(duration, threads, workload).with_affinity(affinity);
+    if let Some(limit) = &args.thermal_limit {
+        config = config.with_thermal_limit(ThermalLimit::parse(limit)?);
+    }
+    if let Some(profile) = &args.profile {
+        let profile = LoadProfile::parse(profile)?;
+        if let Some(threads) = profile.max_threads()
+            && threads > logical_cores
+        {
+            return Err(format!(
+                "Load profile asks for {} threads, this machine has {} logical CPUs",
+                threads, logical_cores
+            ));
+        }
+        config = config.with_profile(profile);
+    } else if let Some(percent) = args.target_load {
+        config = config.with_target_load(percent);
+    }
+    Ok(config)
+}
+
+async fn run_test(args: &RunArgs) -> Result<RunRecord, String> {
+    let mut system_usage = SystemUsage::new();
+    let (logical_cores, _) = system_usage.get_cpu_info();
+    let stress_test = CpuExplosion::new();
+    let config = config(args, &stress_test, logical_cores)?;
+
+    let mut events = stress_test.subscribe();
+    let tester = stress_test.clone();
+    let run_config = config.clone();
+    let mut handle = tokio::spawn(async move { tester.stress_test_cpu(&run_config).await });
+
+    let mut samples = Vec::new();
+    let mut last_progress: Option<Instant> = None;
+    let mut stopping = false;
+    let mut rate_unit = "";
+    let mut on_event = |event: StressEvent| match event {
+        StressEvent::Started {
+            workload,
+            threads,
+            rate_unit: unit,
+            ..
+        } => {
+            rate_unit = unit;
+            eprintln!(
+                "Running {} on {} threads for {}s",
+                workload, threads, config.duration_sec
+            );
+        }
+        StressEvent::Sample(sample) => {
+            let (_, cpus) = system_usage.get_cpu_info();
+            let (used_ram, _) = system_usage.get_ram_info();
+            let temperature = hottest_temperature(&system_usage.get_temperatures());
+            let row = RunSample::new(
+                sample.elapsed.as_secs_f64(),
+                &cpus,
+                used_ram,
+                temperature,
+                Some(&sample),
+            );
+            if last_progress.is_none_or(|at| at.elapsed() >= PROGRESS_INTERVAL) {
+                last_progress = Some(Instant::now());
+                eprintln!("{}", progress_line(&row, config.duration_sec, rate_unit));
+            }
+            samples.push(row);
+        }
+        StressEvent::Error(error) => eprintln!(
+            "Wrong result from thread {} at {:.1}s: {}",
+            error.thread_index,
+            error.at.as_secs_f64(),
+            error.message
+        ),
+        StressEvent::Finished(_) => {}
+    };
+    let result: StressResult = loop {
+        tokio::select! {
+            Some(event) = events.recv() => on_event(event),
+            _ = tokio::signal::ctrl_c(), if !stopping => {
+                stopping = true;
+                eprintln!("Stopping early, the result will still be saved");
+                stress_test.stop_signal.store(true, Ordering::Relaxed);
+            }
+            joined = &mut handle => {
+                // The last samples and errors can still be queued
+                while let Ok(event) = events.try_recv() {
+                    on_event(event);
+                }
+                break joined.map_err(|e| e.to_string())??;
+            }
+        }
+    };
+
+    eprintln!(
+        "{}: score {}, {:.2} {} over {} workers",
+        result.outcome().to_uppercase(),
+        result.score,
+        result.rate,
+        result.rate_unit,
+        result.workers.len()
+    );
+    if result.throttling.throttled() {
+        eprintln!(
+            "Throttled for {:.0}s ({} clock drops, {} rate drops)",
+            result.throttling.throttled_for.as_secs_f64(),
+            result.throttling.frequency_drops,
+            result.throttling.rate_drops
+        );
+    }
+    let settings = RunSettings::new(
+        &config,
+        args.affinity.as_deref().unwrap_or(""),
+        args.profile.as_deref().unwrap_or(""),
+        args.thermal_limit.as_deref().unwrap_or(""),
+    );
+    Ok(RunRecord::new(
+        &result,
+        settings,
+        system_usage.hardware_info(),
+        samples,
+    ))
+}
+
+/// One line of progress, e.g. "[ 40s/300s] 713.62 units/s, usage 99%, 65.0°C, 3400 MHz".
+fn progress_line(row: &RunSample, duration_secs: u64, rate_unit: &str) -> String {
+    let mut line = format!(
+        "[{:>4.0}s/{}s] {:.2} {}, usage {:.0}%",
+        row.elapsed,
+        duration_secs,
+        row.throughput.unwrap_or(0.0),
+        rate_unit,
+        row.usage
+    );
+    if let Some(temperature) = row.temperature {
+        line.push_str(&format!(", {:.1}°C", temperature));
+    }
+    if let Some(frequency) = row.frequency {
+        line.push_str(&format!(", {:.0} MHz", frequency));
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use clap::Parser;
+
+    use super::*;
+    use crate::{
+        cli::{Cli, Command},
+        results::{RecordedError, tests::record},
+    };
+
+    /// Config for `md_ratatui run <args>` on a machine with 4 logical CPUs.
+    fn config_for(args: &[&str]) -> Result<StressConfig, String> {
+        let cli = Cli::try_parse_from(["md_ratatui", "run"].iter().chain(args)).unwrap();
+        let Some(Command::Run(args)) = cli.command else {
+            panic!("not a run command");
+        };
+        config(&args, &CpuExplosion::new(), 4)
+    }
+
+    fn assert_rejected(args: &[&str], error: &str) {
+        match config_for(args) {
+            Ok(_) => panic!("{:?} was accepted", args),
+            Err(e) => assert!(e.contains(error), "{:?}: {}", args, e),
+        }
+    }
+
+    #[test]
+    fn defaults_to_every_cpu_unpinned() {
+        let config = config_for(&["--duration", "90s"]).unwrap();
+        assert_eq!(config.duration_sec, 90);
+        assert_eq!(config.threads, 4);
+        assert_eq!(config.workload, "fibonacci");
+        assert_eq!(config.affinity, CpuAffinity::Unpinned);
+        assert_eq!(config.target_load, None);
+        assert!(config.profile.is_none());
+        assert!(config.thermal_limit.is_none());
+    }
+
+    #[test]
+    fn options_are_applied() {
+        let config = config_for(&[
+            "--duration",
+            "1.5s",
+            "--workload",
+            "fib",
+            "--affinity",
+            "1-2",
+            "--target-load",
+            "50",
+            "--thermal-limit",
+            "95",
+        ])
+        .unwrap();
+        // Partial seconds round up
+        assert_eq!(config.duration_sec, 2);
+        assert_eq!(config.workload, "fibonacci");
+        // One thread per pinned CPU unless --cores says otherwise
+        assert_eq!(config.threads, 2);
+        assert_eq!(config.affinity, CpuAffinity::Cpus(vec![1, 2]));
+        assert_eq!(config.target_load, Some(50.0));
+        assert_eq!(config.thermal_limit, Some(ThermalLimit::new(95.0)));
+
+        let config = config_for(&["--duration", "1m", "--cores", "3"]).unwrap();
+        assert_eq!(config.threads, 3);
+    }
+
+    #[test]
+    fn profile_sets_the_duration() {
+        let config = config_for(&["--profile", "ramp 0 100 60s; hold 50 30s @2"]).unwrap();
+        assert_eq!(config.duration_sec, 90);
+        assert_eq!(config.threads, 4);
+        assert!(config.profile.is_some());
+    }
+
+    #[test]
+    fn rejects_bad_options() {
+        assert_rejected(&["--duration", "1m", "--cores", "0"], "between 1 and 4");
+        assert_rejected(&["--duration", "1m", "--cores", "5"], "between 1 and 4");
+        assert_rejected(&["--duration", "1m", "--affinity", "4"], "does not exist");
+        assert_rejected(
+            &["--duration", "1m", "--workload", "nope"],
+            "Unknown workload",
+        );
+        assert_rejected(
+            &["--duration", "1m", "--thermal-limit", "hot"],
+            "Invalid temperature",
+        );
+    }
+
+    #[test]
+    fn rejects_a_profile_with_a_target_load() {
+        assert_rejected(
+            &["--profile", "hold 50 1m", "--target-load", "80"],
+            "cannot be combined",
+        );
+    }
+
+    #[test]
+    fn rejects_profiles_asking_for_more_threads_than_cpus() {
+        assert_rejected(&["--profile", "hold 50 1m @8"], "asks for 8 threads");
+        assert!(config_for(&["--profile", "hold 50 1m @4"]).is_ok());
+    }
+
+    #[test]
+    fn exit_code_tells_how_the_run_ended() {
+        let passed = record("fibonacci", 100, Utc::now());
+        assert_eq!(exit_code(&passed, None), ExitCode::SUCCESS);
+        assert_eq!(exit_code(&passed, Some(100)), ExitCode::SUCCESS);
+        assert_eq!(
+            exit_code(&passed, Some(101)),
+            ExitCode::from(EXIT_BELOW_THRESHOLD)
+        );
+
+        let mut failed = passed.clone();
+        failed.errors.push(RecordedError {
+            thread_index: 0,
+            core: None,
+            at_secs: 1.0,
+            message: "wrong".to_string(),
+        });
+        // Wrong results count more than the score
+        assert_eq!(exit_code(&failed, Some(101)), ExitCode::from(EXIT_FAILED));
+
+        let mut aborted = failed.clone();
+        aborted.thermal_abort = Some("package 96.0°C at 10s, reached 95°C".to_string());
+        assert_eq!(
+            exit_code(&aborted, None),
+            ExitCode::from(EXIT_THERMAL_ABORT)
+        );
+    }
+}
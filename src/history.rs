@@ -0,0 +1,329 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::Local;
+
+use crate::results::RunRecord;
+
+/// Column the run history is ordered by.
+#[derive(Clone, Copy, PartialEq)]
+pub enum HistorySort {
+    Date,
+    Machine,
+    Workload,
+    Cores,
+    Duration,
+    Score,
+}
+
+impl HistorySort {
+    pub fn next(self) -> Self {
+        match self {
+            HistorySort::Date => HistorySort::Machine,
+            HistorySort::Machine => HistorySort::Workload,
+            HistorySort::Workload => HistorySort::Cores,
+            HistorySort::Cores => HistorySort::Duration,
+            HistorySort::Duration => HistorySort::Score,
+            HistorySort::Score => HistorySort::Date,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            HistorySort::Date => "date",
+            HistorySort::Machine => "machine",
+            HistorySort::Workload => "workload",
+            HistorySort::Cores => "cores",
+            HistorySort::Duration => "duration",
+            HistorySort::Score => "score",
+        }
+    }
+}
+
+/// Saved runs read back from the results directory, with the sorting and filtering
+/// of the history screen.
+pub struct RunHistory {
+    pub records: Vec<(PathBuf, RunRecord)>,
+    /// Files that could not be read, with the reason.
+    pub errors: Vec<String>,
+    pub sort: HistorySort,
+    pub descending: bool,
+    /// Only runs whose machine, workload, outcome or date contain this are listed.
+    pub filter: String,
+    /// Index into `visible()` of the highlighted run.
+    pub selected: usize,
+    /// Run picked as the first side of a comparison.
+    pub marked: Option<PathBuf>,
+}
+
+impl RunHistory {
+    pub fn new() -> Self {
+        RunHistory {
+            records: Vec::new(),
+            errors: Vec::new(),
+            sort: HistorySort::Date,
+            descending: true,
+            filter: String::new(),
+            selected: 0,
+            marked: None,
+        }
+    }
+
+    /// Reads every `.json` record in `dir`. A missing directory just means no runs yet.
+    pub fn load(&mut self, dir: &Path) {
+        self.records.clear();
+        self.errors.clear();
+        self.selected = 0;
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            match RunRecord::load(&path) {
+                Ok(record) => self.records.push((path, record)),
+                Err(e) => self.errors.push(e),
+            }
+        }
+        self.sort_records();
+    }
+
+    pub fn set_sort(&mut self, sort: HistorySort) {
+        self.sort = sort;
+        self.sort_records();
+    }
+
+    pub fn reverse(&mut self) {
+        self.descending = !self.descending;
+        self.sort_records();
+    }
+
+    fn sort_records(&mut self) {
+        let (sort, descending) = (self.sort, self.descending);
+        self.records.sort_by(|(_, a), (_, b)| {
+            let order = match sort {
+                HistorySort::Date => a.started_at.cmp(&b.started_at),
+                HistorySort::Machine => a.machine.hostname.cmp(&b.machine.hostname),
+                HistorySort::Workload => a.settings.workload.cmp(&b.settings.workload),
+                HistorySort::Cores => a.settings.threads.cmp(&b.settings.threads),
+                HistorySort::Duration => a.elapsed_secs.total_cmp(&b.elapsed_secs),
+                HistorySort::Score => a.score.cmp(&b.score),
+            };
+            let order = if descending { order.reverse() } else { order };
+            // Ties keep the newest run first
+            order.then(b.started_at.cmp(&a.started_at))
+        });
+    }
+
+    /// Runs that pass the filter, in display order.
+    pub fn visible(&self) -> Vec<&(PathBuf, RunRecord)> {
+        let filter = self.filter.to_lowercase();
+        self.records
+            .iter()
+            .filter(|(_, record)| {
+                filter.is_empty()
+                    || [
+                        &record.machine.hostname,
+                        &record.machine.cpu_brand,
+                        &record.settings.workload,
+                        &record.outcome,
+                        &start_time(record),
+                    ]
+                    .iter()
+                    .any(|field| field.to_lowercase().contains(&filter))
+            })
+            .collect()
+    }
+
+    pub fn selected_record(&self) -> Option<&RunRecord> {
+        self.visible().get(self.selected).map(|(_, record)| record)
+    }
+
+    /// Marks the highlighted run for comparison. Once a run is marked, returns it
+    /// together with the highlighted one, oldest first.
+    pub fn mark_for_comparison(&mut self) -> Option<(RunRecord, RunRecord)> {
+        let path = self.visible().get(self.selected)?.0.clone();
+        let Some(marked) = self.marked.take() else {
+            self.marked = Some(path);
+            return None;
+        };
+        if marked == path {
+            return None; // Marking the same run again clears the mark
+        }
+        let record = |path: &PathBuf| {
+            self.records
+                .iter()
+                .find(|(p, _)| p == path)
+                .map(|(_, record)| record.clone())
+        };
+        let mut pair = (record(&marked)?, record(&path)?);
+        if pair.0.started_at > pair.1.started_at {
+            pair = (pair.1, pair.0);
+        }
+        Some(pair)
+    }
+
+    /// Moves the highlight by `step` rows, staying within the visible runs.
+    pub fn move_selection(&mut self, step: isize) {
+        let count = self.visible().len();
+        self.selected = self
+            .selected
+            .saturating_add_signed(step)
+            .min(count.saturating_sub(1));
+    }
+}
+
+impl Default for RunHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// When the run started, in local time.
+pub fn start_time(record: &RunRecord) -> String {
+    record
+        .started_at
+        .with_timezone(&Local)
+        .format("%Y-%m-%d %H:%M")
+        .to_string()
+}
+
+/// Duration such as "45s", "10m" or "1h30m".
+pub fn format_duration(secs: f64) -> String {
+    let secs = secs.round() as u64;
+    match secs {
+        0..60 => format!("{}s", secs),
+        60..3600 if secs.is_multiple_of(60) => format!("{}m", secs / 60),
+        60..3600 => format!("{}m{}s", secs / 60, secs % 60),
+        _ => format!("{}h{}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, TimeZone, Utc};
+
+    use super::*;
+    use crate::results::tests::record;
+
+    fn at(hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 3, 1, hour, 0, 0).unwrap()
+    }
+
+    /// Three runs: fibonacci at 10:00 scoring 300, torture at 11:00 scoring 100 and
+    /// fibonacci on another machine at 12:00 scoring 200.
+    fn history() -> RunHistory {
+        let mut other_machine = record("fibonacci", 200, at(12));
+        other_machine.machine.hostname = "alpha".to_string();
+        other_machine.settings.threads = 8;
+        let mut history = RunHistory::new();
+        history.records = vec![
+            ("a.json".into(), record("fibonacci", 300, at(10))),
+            ("b.json".into(), record("torture", 100, at(11))),
+            ("c.json".into(), other_machine),
+        ];
+        history.set_sort(HistorySort::Date);
+        history
+    }
+
+    fn order(history: &RunHistory) -> Vec<String> {
+        history
+            .visible()
+            .iter()
+            .map(|(path, _)| path.display().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn newest_runs_come_first() {
+        assert_eq!(order(&history()), ["c.json", "b.json", "a.json"]);
+    }
+
+    #[test]
+    fn sorts_by_each_column() {
+        let mut history = history();
+        history.set_sort(HistorySort::Score);
+        assert_eq!(order(&history), ["a.json", "c.json", "b.json"]);
+        history.reverse();
+        assert_eq!(order(&history), ["b.json", "c.json", "a.json"]);
+        history.set_sort(HistorySort::Cores);
+        assert_eq!(order(&history), ["b.json", "a.json", "c.json"]);
+        history.set_sort(HistorySort::Machine);
+        assert_eq!(order(&history), ["c.json", "b.json", "a.json"]);
+    }
+
+    #[test]
+    fn ties_keep_the_newest_run_first() {
+        let mut history = history();
+        history.set_sort(HistorySort::Workload);
+        assert_eq!(order(&history), ["b.json", "c.json", "a.json"]);
+        history.reverse();
+        assert_eq!(order(&history), ["c.json", "a.json", "b.json"]);
+    }
+
+    #[test]
+    fn filter_matches_any_field_ignoring_case() {
+        let mut history = history();
+        history.filter = "FIB".to_string();
+        assert_eq!(order(&history), ["c.json", "a.json"]);
+        history.filter = "alpha".to_string();
+        assert_eq!(order(&history), ["c.json"]);
+        history.filter = "test cpu".to_string();
+        assert_eq!(order(&history).len(), 3);
+        history.filter = "passed".to_string();
+        assert_eq!(order(&history).len(), 3);
+        history.filter = "2026-03-01".to_string();
+        assert_eq!(order(&history).len(), 3);
+        history.filter = "nothing".to_string();
+        assert!(order(&history).is_empty());
+        assert!(history.selected_record().is_none());
+    }
+
+    #[test]
+    fn selection_stays_within_the_visible_runs() {
+        let mut history = history();
+        history.move_selection(10);
+        assert_eq!(history.selected, 2);
+        history.move_selection(-10);
+        assert_eq!(history.selected, 0);
+        history.filter = "torture".to_string();
+        history.move_selection(1);
+        assert_eq!(history.selected, 0);
+        assert_eq!(history.selected_record().unwrap().score, 100);
+    }
+
+    #[test]
+    fn mark_survives_sorting() {
+        let mut history = history();
+        // Mark the newest run, then find the oldest after sorting by score
+        assert!(history.mark_for_comparison().is_none());
+        assert_eq!(history.marked, Some(PathBuf::from("c.json")));
+        history.set_sort(HistorySort::Score);
+        history.selected = 0;
+        assert_eq!(history.selected_record().unwrap().score, 300);
+
+        let (older, newer) = history.mark_for_comparison().unwrap();
+        assert_eq!(older.started_at, at(10));
+        assert_eq!(newer.started_at, at(12));
+        assert_eq!(history.marked, None);
+    }
+
+    #[test]
+    fn marking_the_same_run_twice_clears_the_mark() {
+        let mut history = history();
+        assert!(history.mark_for_comparison().is_none());
+        assert!(history.mark_for_comparison().is_none());
+        assert_eq!(history.marked, None);
+    }
+
+    #[test]
+    fn durations_are_short() {
+        assert_eq!(format_duration(45.0), "45s");
+        assert_eq!(format_duration(600.0), "10m");
+        assert_eq!(format_duration(90.0), "1m30s");
+        assert_eq!(format_duration(5400.0), "1h30m");
+    }
+}
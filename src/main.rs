@@ -1,12 +1,22 @@
-use std::{io, time::Instant};
+use std::{
+    io,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::atomic::Ordering,
+    time::Instant,
+};
 
-use color_eyre::owo_colors::DynColor;
+use clap::Parser;
 use crossterm::{
     event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
-use md_hardware::{CpuExplosion, CpuUsage, SystemUsage};
+use md_hardware::{
+    ComputationError, CpuAffinity, CpuUsage, LoadProfile, MAX_TIME, MemTest, StressConfig,
+    StressEvent, StressResult, SystemUsage, Temperature, ThermalLimit, ThroughputSample,
+    hottest_temperature,
+};
 use ratatui::{
     Frame, Terminal,
     backend::CrosstermBackend,
@@ -14,25 +24,84 @@ use ratatui::{
     style::{Color, Modifier, Style},
     symbols,
     text::{Line, Span, Text},
-    widgets::{Axis, Block, Borders, Chart, Dataset, GraphType, Paragraph},
+    widgets::{
+        Axis, Block, Borders, Cell, Chart, Dataset, GraphType, Paragraph, Row, Table, TableState,
+    },
+};
+use tokio::{sync::mpsc::UnboundedReceiver, task::JoinHandle, time::Duration};
+
+use crate::{
+    cli::{Cli, Command, FormArgs},
+    history::{RunHistory, format_duration, start_time},
+    plan::{PLANS_DIR, PlanReport, PlanRun, StageOutcome, StageReport, TestPlan},
+    results::{RESULTS_DIR, RunRecord, RunSample, RunSamples, RunSettings},
+    scaling::{ScalingReport, sweep_plan},
+    theme::Theme,
+    timeseries::{TimeSeries, Viewport},
 };
-use tokio::{task::JoinHandle, time::Duration};
+
+mod cli;
+mod headless;
+mod history;
+mod plan;
+mod results;
+mod scaling;
+mod theme;
+mod timeseries;
 
 enum Mode {
     Input,
     Chart,
     Finished,
+    History,
+    Compare,
+    PlanReport,
+}
+
+/// How the top chart in Chart mode shows CPU usage.
+#[derive(Clone, Copy)]
+enum UsageView {
+    Average,
+    PerCore,
+    Heatmap,
+}
+
+impl UsageView {
+    fn next(self) -> Self {
+        match self {
+            UsageView::Average => UsageView::PerCore,
+            UsageView::PerCore => UsageView::Heatmap,
+            UsageView::Heatmap => UsageView::Average,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            UsageView::Average => "average",
+            UsageView::PerCore => "per core",
+            UsageView::Heatmap => "heatmap",
+        }
+    }
 }
 
+#[derive(Clone, Copy, clap::ValueEnum)]
 enum TimeUnit {
     Seconds,
     Minutes,
 }
 
+/// Shown below the form when the duration field does not hold a usable duration.
+const INVALID_DURATION: &str = "Duration must be a whole number of seconds or minutes, at least 1";
+
 enum InputFocusElement {
     ValueInput,
     UnitSelection,
     CpuCountSelection,
+    AffinityInput,
+    TargetLoadInput,
+    ProfileInput,
+    ThermalLimitInput,
+    WorkloadSelection,
     OkButton,
 }
 
@@ -40,7 +109,10 @@ struct App {
     mode: Mode,
     input_text: String,
     selected_unit: TimeUnit,
-    chart_data: Vec<(f64, f64)>, // (time_elapsed, value)
+    chart_data: TimeSeries,           // (time_elapsed, value)
+    core_usage_data: Vec<TimeSeries>, // (time_elapsed, usage) for every logical CPU
+    viewport: Viewport,               // Zoom and scroll position of the charts
+    usage_view: UsageView,            // Average line, per-core lines or heatmap
     start_time: Option<Instant>,
     total_duration_secs: u64,
     elapsed_secs: u64,
@@ -50,10 +122,42 @@ struct App {
     last_cpu_refresh: Instant,              // Track last CPU refresh time
     cpu_refresh_interval: Duration,         // Interval for CPU refresh
     cpu_info_cached: Vec<CpuUsage>,         // Cache for CPU info (now custom CpuInfo)
+    temperatures_cached: Vec<Temperature>,  // Sensor readings, refreshed with the CPU info
+    temperature_data: TimeSeries,           // (time_elapsed, hottest sensor in °C)
+    frequency_data: TimeSeries,             // (time_elapsed, workers' mean frequency in MHz)
+    core_frequency_data: Vec<TimeSeries>,   // (time_elapsed, MHz) for every logical CPU
+    throughput_data: TimeSeries,            // (time_elapsed, workload rate over the interval)
     total_logical_cores: usize,             // Total logical cores available
     selected_cpu_count: String,             // Number of CPU cores selected by the user
+    selected_affinity: String,              // CPU list to pin workers to, e.g. "0,2,4-7"
+    selected_target_load: String,           // CPU usage % to hold, empty for full load
+    selected_profile: String,               // Load profile, e.g. "ramp 0 100 60s; hold 100 5m"
+    profile_points: Vec<(f64, f64)>,        // Planned load of the running profile (time, %)
+    selected_thermal_limit: String,         // e.g. "95, 85 for 30s", empty for no limit
+    selected_workload: usize,               // Index into the stress test's workload registry
+    form_errors: Vec<String>,               // What stopped the form from starting a test
     stress_test: md_hardware::CpuExplosion,
-    stress_test_handle: Option<JoinHandle<u64>>,
+    stress_test_handle: Option<JoinHandle<Result<StressResult, String>>>,
+    last_result: Option<Result<StressResult, String>>, // Outcome shown in the finished popup
+    stress_events: Option<UnboundedReceiver<StressEvent>>, // Progress of the running test
+    rate_unit: String,                                 // Unit of the running workload's rate
+    last_sample: Option<ThroughputSample>,             // Most recent throughput sample
+    run_errors: Vec<ComputationError>,                 // Errors reported so far in this run
+    run_config: Option<StressConfig>,                  // Configuration of the running test
+    run_samples: RunSamples,                           // Samples of the run, for saving
+    saved_record: Option<Result<PathBuf, String>>,     // Where the finished run was saved
+    history: RunHistory,                               // Saved runs listed in History mode
+    history_filtering: bool,                           // Keys go to the history filter
+    reviewing: Option<RunRecord>,                      // Saved run shown read-only in Chart mode
+    comparison: Option<(RunRecord, RunRecord)>,        // Older and newer run in Compare mode
+    default_duration: String,                          // Duration the form starts with
+    default_cpu_count: String,                         // Core count the form starts with
+    theme: Theme,                                      // Colours of the interface
+    plan: Option<PlanRun>,                             // Test plan being run stage by stage
+    stage_handle: Option<JoinHandle<Result<StageOutcome, String>>>, // Running plan stage
+    memtest: MemTest,                                  // Runs the memory stages of plans
+    plan_report: Option<PlanReport>,                   // Combined report of the finished plan
+    saved_plan_report: Option<Result<PathBuf, String>>, // Where the plan report was saved
 }
 
 /// Options available in the "Time's Up!" popup.
@@ -72,7 +176,10 @@ impl App {
             mode: Mode::Input,
             input_text: String::new(),
             selected_unit: TimeUnit::Seconds,
-            chart_data: Vec::new(),
+            chart_data: TimeSeries::new(),
+            core_usage_data: Vec::new(),
+            viewport: Viewport::new(),
+            usage_view: UsageView::Average,
             start_time: None,
             total_duration_secs: 0,
             elapsed_secs: 0,
@@ -82,73 +189,316 @@ impl App {
             last_cpu_refresh: Instant::now(),
             cpu_refresh_interval: Duration::from_secs(1), // Refresh CPU every 1 second
             cpu_info_cached: initial_cpus,                // Store initial CPU info
-            total_logical_cores,                          // Initialize with actual core count
-            selected_cpu_count: String::new(),            // Default to 1 selected core
+            temperatures_cached: Vec::new(),
+            temperature_data: TimeSeries::new(),
+            frequency_data: TimeSeries::new(),
+            core_frequency_data: Vec::new(),
+            throughput_data: TimeSeries::new(),
+            total_logical_cores, // Initialize with actual core count
+            selected_cpu_count: String::new(), // Default to 1 selected core
+            selected_affinity: String::new(),
+            selected_target_load: String::new(),
+            selected_profile: String::new(),
+            profile_points: Vec::new(),
+            selected_thermal_limit: String::new(),
+            selected_workload: 0,
+            form_errors: Vec::new(),
             stress_test: md_hardware::CpuExplosion::new(),
             stress_test_handle: None,
+            last_result: None,
+            stress_events: None,
+            rate_unit: String::new(),
+            last_sample: None,
+            run_errors: Vec::new(),
+            run_config: None,
+            run_samples: RunSamples::new(),
+            saved_record: None,
+            history: RunHistory::new(),
+            history_filtering: false,
+            reviewing: None,
+            comparison: None,
+            default_duration: String::new(),
+            default_cpu_count: String::new(),
+            theme: Theme::default(),
+            plan: None,
+            stage_handle: None,
+            memtest: MemTest::new(),
+            plan_report: None,
+            saved_plan_report: None,
+        }
+    }
+
+    /// Fills the input form from the command line, so it starts pre-filled on every
+    /// run. With `--start` the test begins right away.
+    fn apply_form_args(&mut self, args: &FormArgs) -> Result<(), String> {
+        if let Some(workload) = &args.workload {
+            let name = self.stress_test.workloads().resolve(workload)?.to_string();
+            let names = self.stress_test.workloads().names();
+            self.selected_workload = names.iter().position(|n| *n == name).unwrap_or(0);
+        }
+        if let Some(cores) = args.cores {
+            if cores == 0 || cores > self.total_logical_cores {
+                return Err(format!(
+                    "--cores must be between 1 and {}",
+                    self.total_logical_cores
+                ));
+            }
+            self.default_cpu_count = cores.to_string();
+        }
+        if let Some(duration) = args.duration {
+            self.default_duration = duration.to_string();
+        }
+        self.selected_unit = args.unit;
+        self.theme = args.theme;
+        self.input_text = self.default_duration.clone();
+        self.selected_cpu_count = self.default_cpu_count.clone();
+        if let Some(path) = &args.plan {
+            let plan =
+                TestPlan::load(path, self.stress_test.workloads(), self.total_logical_cores)?;
+            self.start_plan(plan);
+        } else if args.sweep {
+            self.start_sweep();
+        } else if args.start {
+            // Nothing to pin to from the command line, so use every logical CPU
+            if self.selected_cpu_count.is_empty() && self.selected_affinity.trim().is_empty() {
+                self.selected_cpu_count = self.total_logical_cores.to_string();
+            }
+            self.set_total_duration();
         }
+        Ok(())
     }
 
     /// Resets the application state to prepare for new input.
     fn reset_for_input(&mut self) {
         self.mode = Mode::Input;
-        self.input_text.clear();
+        self.form_errors.clear();
+        self.input_text = self.default_duration.clone();
         self.chart_data.clear();
+        self.core_usage_data.clear();
+        self.viewport.reset();
+        self.temperature_data.clear();
+        self.frequency_data.clear();
+        self.core_frequency_data.clear();
+        self.throughput_data.clear();
         self.start_time = None;
         self.total_duration_secs = 0;
         self.elapsed_secs = 0;
         self.current_input_focus = InputFocusElement::ValueInput;
         self.finished_popup_selected_option = PopupOption::RunAgain;
         self.stress_test_handle = None;
-        self.stress_test = CpuExplosion::new();
+        self.last_result = None;
+        self.stress_events = None;
+        self.last_sample = None;
+        self.run_errors.clear();
+        self.run_config = None;
+        self.run_samples.clear();
+        self.saved_record = None;
+        self.reviewing = None;
+        self.plan = None;
+        self.stage_handle = None;
+        self.plan_report = None;
+        self.saved_plan_report = None;
+        // Stop a test that is still running and keep its worker threads for the next one
+        self.stress_test.stop_signal.store(true, Ordering::Relaxed);
+        self.stress_test = self.stress_test.next_run();
+        self.memtest.stop_signal.store(true, Ordering::Relaxed);
+        self.memtest = MemTest::new();
         // Re-initialize SystemUsage to clear previous data and get fresh system info
         self.system_usage = SystemUsage::new();
         let (_, initial_cpus) = self.system_usage.get_cpu_info();
         self.cpu_info_cached = initial_cpus;
         self.last_cpu_refresh = Instant::now();
-        self.selected_cpu_count = String::new(); // Reset selected CPU count
+        self.selected_cpu_count = self.default_cpu_count.clone(); // Reset selected CPU count
+        self.selected_affinity = String::new();
+        self.selected_target_load = String::new();
+        self.selected_profile = String::new();
+        self.profile_points.clear();
+        self.selected_thermal_limit = String::new();
     }
 
-    /// Parses the input text and selected unit to set the total duration.
+    /// Name of the workload currently selected in the input form.
+    fn selected_workload_name(&self) -> String {
+        let names = self.stress_test.workloads().names();
+        names[self.selected_workload % names.len()].to_owned()
+    }
+
+    /// Moves the workload selection forwards or backwards through the registry.
+    fn cycle_workload(&mut self, forward: bool) {
+        let count = self.stress_test.workloads().names().len();
+        self.selected_workload = if forward {
+            (self.selected_workload + 1) % count
+        } else {
+            (self.selected_workload + count - 1) % count
+        };
+    }
+
+    /// Checks every field of the form and starts the test, or lists what is wrong
+    /// below the form and leaves the app as it was.
     fn set_total_duration(&mut self) {
-        if let Ok(value) = self.input_text.parse::<u64>() {
-            self.total_duration_secs = match self.selected_unit {
-                TimeUnit::Seconds => value,
-                TimeUnit::Minutes => value * 60,
-            };
-            self.start_time = Some(Instant::now());
-            self.elapsed_secs = 0;
-            let duration_for_stress_test = self.total_duration_secs;
-            let cores_for_stress_test: usize = self.selected_cpu_count.parse().unwrap(); // Use selected_cpu_count for the test
-            let stress_tester = self.stress_test.clone(); // Clone if CpuExplosion can be cloned, or pass by Arc/Rc
-
-            self.stress_test_handle = Some(tokio::spawn(async move {
-                stress_tester
-                    .stress_test_cpu(duration_for_stress_test, cores_for_stress_test)
-                    .await
-            }));
-            self.mode = Mode::Chart;
+        self.form_errors.clear();
+        // A load profile brings its own duration
+        let profile = if self.selected_profile.trim().is_empty() {
+            None
+        } else {
+            LoadProfile::parse(&self.selected_profile)
+                .map_err(|e| self.form_errors.push(e))
+                .ok()
+        };
+        let duration = match &profile {
+            Some(profile) => Some(profile.duration().as_secs_f64().ceil() as u64),
+            // The profile's error is listed already and it would replace the duration
+            None if !self.selected_profile.trim().is_empty() => None,
+            None => {
+                let duration = self.form_duration_secs();
+                if duration.is_none() {
+                    self.form_errors.push(INVALID_DURATION.to_string());
+                }
+                duration
+            }
+        };
+        let affinity = CpuAffinity::parse(&self.selected_affinity)
+            .map_err(|e| self.form_errors.push(e))
+            .ok();
+        let pinned_cpus = match &affinity {
+            Some(affinity) => affinity
+                .resolve(self.total_logical_cores)
+                .map_err(|e| self.form_errors.push(e))
+                .ok(),
+            None => None,
+        };
+        // Without an explicit core count, run one worker per pinned CPU
+        let cores_for_stress_test = match self.selected_cpu_count.parse::<usize>() {
+            Ok(cores) if (1..=self.total_logical_cores).contains(&cores) => Some(cores),
+            Ok(_) => {
+                self.form_errors.push(format!(
+                    "Cores must be between 1 and {}",
+                    self.total_logical_cores
+                ));
+                None
+            }
+            Err(_) => match &pinned_cpus {
+                Some(Some(cpus)) => Some(cpus.len()),
+                Some(None) => {
+                    self.form_errors
+                        .push("Enter a core count or the CPUs to pin to".to_string());
+                    None
+                }
+                // The affinity's error is listed already
+                None => None,
+            },
+        };
+        let thermal_limit = self.form_thermal_limit();
+        let target_load = if profile.is_none() && !self.selected_target_load.is_empty() {
+            match self.selected_target_load.parse::<f32>() {
+                Ok(percent) if percent > 0.0 && percent <= 100.0 => Some(percent),
+                _ => {
+                    self.form_errors.push(format!(
+                        "Target load must be between 0 and 100%, got {}",
+                        self.selected_target_load
+                    ));
+                    None
+                }
+            }
         } else {
-            self.input_text = "Invalid input".to_string(); // Simple error feedback
+            None
+        };
+        let (Some(duration), Some(affinity), Some(cores_for_stress_test), Some(thermal_limit)) =
+            (duration, affinity, cores_for_stress_test, thermal_limit)
+        else {
+            return;
+        };
+        if !self.form_errors.is_empty() {
+            return;
+        }
+
+        let mut config = StressConfig::new(
+            duration,
+            cores_for_stress_test,
+            self.selected_workload_name(),
+        )
+        .with_affinity(affinity);
+        if let Some(limit) = thermal_limit {
+            config = config.with_thermal_limit(limit);
+        }
+        if let Some(profile) = profile {
+            // About 200 points, enough for a smooth line at any terminal width
+            let step =
+                Duration::from_secs_f64(duration as f64 / 200.0).max(Duration::from_millis(100));
+            self.profile_points = profile.points(step);
+            config = config.with_profile(profile);
+        } else if let Some(percent) = target_load {
+            config = config.with_target_load(percent);
+        }
+        self.total_duration_secs = duration;
+        self.start_time = Some(Instant::now());
+        self.elapsed_secs = 0;
+        self.run_config = Some(config.clone());
+        let stress_tester = self.stress_test.clone(); // Clone if CpuExplosion can be cloned, or pass by Arc/Rc
+        self.stress_events = Some(self.stress_test.subscribe());
+
+        self.stress_test_handle = Some(tokio::spawn(async move {
+            stress_tester.stress_test_cpu(&config).await
+        }));
+        self.mode = Mode::Chart;
+    }
+
+    /// Duration typed into the form, in seconds. `None` unless it is at least a second
+    /// and no longer than a load profile may last.
+    fn form_duration_secs(&self) -> Option<u64> {
+        let value = self.input_text.parse::<u64>().ok()?;
+        match self.selected_unit {
+            TimeUnit::Seconds => Some(value),
+            TimeUnit::Minutes => value.checked_mul(60),
+        }
+        .filter(|secs| (1..=MAX_TIME.as_secs()).contains(secs))
+    }
+
+    /// Thermal limit typed into the form. `Some(None)` when the field is empty, `None`
+    /// after adding the parse error to the form errors.
+    fn form_thermal_limit(&mut self) -> Option<Option<ThermalLimit>> {
+        if self.selected_thermal_limit.trim().is_empty() {
+            return Some(None);
         }
+        ThermalLimit::parse(&self.selected_thermal_limit)
+            .map(Some)
+            .map_err(|e| self.form_errors.push(e))
+            .ok()
     }
 
     fn update_data(&mut self) {
+        self.handle_stress_events();
+
         if let Some(start) = self.start_time {
             let now = Instant::now();
-            let new_elapsed = (now - start).as_secs();
+            // Paused time does not count towards the test duration
+            let new_elapsed = (now - start)
+                .saturating_sub(self.stress_test.pause.paused_for())
+                .as_secs();
 
             if new_elapsed > self.elapsed_secs {
                 let (_, cpus) = self.system_usage.get_cpu_info();
                 let chart_value = avg_percent_usage_cpu(&cpus);
                 self.elapsed_secs = new_elapsed;
-                self.chart_data
-                    .push((self.elapsed_secs as f64, chart_value));
-
-                let max_data_points = 100;
-                if self.chart_data.len() > max_data_points {
-                    self.chart_data.remove(0);
+                self.chart_data.push(self.elapsed_secs as f64, chart_value);
+                self.core_usage_data
+                    .resize_with(cpus.len(), TimeSeries::new);
+                for (history, cpu) in self.core_usage_data.iter_mut().zip(&cpus) {
+                    history.push(self.elapsed_secs as f64, cpu.usage as f64);
                 }
+                self.temperatures_cached = self.system_usage.get_temperatures();
+                let hottest = hottest_temperature(&self.temperatures_cached);
+                if let Some(hottest) = hottest {
+                    self.temperature_data
+                        .push(self.elapsed_secs as f64, hottest as f64);
+                }
+                let (used_ram, _) = self.system_usage.get_ram_info();
+                self.run_samples.push(RunSample::new(
+                    self.elapsed_secs as f64,
+                    &cpus,
+                    used_ram,
+                    hottest,
+                    self.last_sample.as_ref(),
+                ));
             }
         }
 
@@ -161,6 +511,242 @@ impl App {
     }
 }
 
+impl App {
+    /// Applies the progress events the running test has published since the last frame.
+    fn handle_stress_events(&mut self) {
+        let Some(events) = &mut self.stress_events else {
+            return;
+        };
+        while let Ok(event) = events.try_recv() {
+            match event {
+                StressEvent::Started { rate_unit, .. } => {
+                    self.rate_unit = rate_unit.to_string();
+                }
+                StressEvent::Sample(sample) => {
+                    // Plan stages time their samples from the start of the stage
+                    let elapsed = sample.elapsed.as_secs_f64()
+                        + self.plan.as_ref().map_or(0.0, |run| run.stage_start);
+                    self.throughput_data.push(elapsed, sample.rate);
+                    if let Some(frequency) = sample.frequency {
+                        self.frequency_data.push(elapsed, frequency);
+                    }
+                    self.core_frequency_data
+                        .resize_with(sample.frequencies.len(), TimeSeries::new);
+                    for (history, &frequency) in
+                        self.core_frequency_data.iter_mut().zip(&sample.frequencies)
+                    {
+                        if frequency > 0 {
+                            history.push(elapsed, frequency as f64);
+                        }
+                    }
+                    self.last_sample = Some(sample);
+                }
+                StressEvent::Error(error) => self.run_errors.push(error),
+                StressEvent::Finished(_) => {}
+            }
+        }
+    }
+}
+
+impl App {
+    /// Writes the finished run to the results directory.
+    fn save_result(&mut self) {
+        let (Some(Ok(result)), Some(config)) = (&self.last_result, &self.run_config) else {
+            return;
+        };
+        let settings = RunSettings::new(
+            config,
+            &self.selected_affinity,
+            &self.selected_profile,
+            &self.selected_thermal_limit,
+        );
+        let record = RunRecord::new(
+            result,
+            settings,
+            self.system_usage.hardware_info(),
+            self.run_samples.take(),
+        );
+        self.saved_record = Some(record.save(Path::new(RESULTS_DIR)));
+    }
+
+    /// Lists the runs saved in the results directory.
+    fn open_history(&mut self) {
+        self.history.load(Path::new(RESULTS_DIR));
+        self.history_filtering = false;
+        self.mode = Mode::History;
+    }
+
+    /// Shows a saved run in Chart mode, drawn from its recorded samples.
+    fn open_record(&mut self, record: RunRecord) {
+        self.chart_data = record.series(|sample| Some(sample.usage));
+        self.temperature_data = record.series(|sample| sample.temperature.map(f64::from));
+        self.throughput_data = record.series(|sample| sample.throughput);
+        self.frequency_data = record.series(|sample| sample.frequency);
+        self.core_usage_data.clear();
+        self.core_frequency_data.clear();
+        for sample in &record.samples {
+            self.core_usage_data
+                .resize_with(sample.cores.len(), TimeSeries::new);
+            for (history, usage) in self.core_usage_data.iter_mut().zip(&sample.cores) {
+                history.push(sample.elapsed, *usage as f64);
+            }
+            if self.core_frequency_data.len() < sample.core_frequencies.len() {
+                self.core_frequency_data
+                    .resize_with(sample.core_frequencies.len(), TimeSeries::new);
+            }
+            for (history, &frequency) in self
+                .core_frequency_data
+                .iter_mut()
+                .zip(&sample.core_frequencies)
+            {
+                if frequency > 0 {
+                    history.push(sample.elapsed, frequency as f64);
+                }
+            }
+        }
+        self.viewport.reset();
+        self.total_duration_secs = record.settings.duration_secs;
+        self.elapsed_secs = record.elapsed_secs.round() as u64;
+        self.rate_unit = record.rate_unit.clone();
+        self.profile_points = match record.settings.profile.as_deref().map(LoadProfile::parse) {
+            Some(Ok(profile)) => {
+                let step = Duration::from_secs_f64(self.total_duration_secs as f64 / 200.0)
+                    .max(Duration::from_millis(100));
+                profile.points(step)
+            }
+            _ => Vec::new(),
+        };
+        self.reviewing = Some(record);
+        self.mode = Mode::Chart;
+    }
+
+    /// Leaves a saved run and goes back to the history list.
+    fn close_record(&mut self) {
+        self.reviewing = None;
+        self.chart_data.clear();
+        self.core_usage_data.clear();
+        self.temperature_data.clear();
+        self.frequency_data.clear();
+        self.core_frequency_data.clear();
+        self.throughput_data.clear();
+        self.profile_points.clear();
+        self.total_duration_secs = 0;
+        self.elapsed_secs = 0;
+        self.mode = Mode::History;
+    }
+
+    /// Seconds of the run shown in the charts.
+    fn chart_window(&self) -> (f64, f64) {
+        self.viewport
+            .window(self.total_duration_secs as f64, self.elapsed_secs as f64)
+    }
+}
+
+impl App {
+    /// Runs the stages of `plan` one after another in Chart mode.
+    fn start_plan(&mut self, plan: TestPlan) {
+        self.total_duration_secs = plan.duration_secs();
+        self.start_time = Some(Instant::now());
+        self.elapsed_secs = 0;
+        self.plan = Some(PlanRun::new(plan));
+        self.start_stage();
+        self.mode = Mode::Chart;
+    }
+
+    /// Runs the selected workload on 1, 2, 4, … up to every logical CPU, each for the
+    /// duration typed into the form.
+    fn start_sweep(&mut self) {
+        self.form_errors.clear();
+        let step_secs = self.form_duration_secs();
+        if step_secs.is_none() {
+            self.form_errors.push(INVALID_DURATION.to_string());
+        }
+        let (Some(step_secs), Some(thermal_limit)) = (step_secs, self.form_thermal_limit()) else {
+            return;
+        };
+        let plan = sweep_plan(
+            &self.selected_workload_name(),
+            step_secs,
+            self.total_logical_cores,
+            thermal_limit,
+        );
+        self.start_plan(plan);
+    }
+
+    fn start_stage(&mut self) {
+        // Workers set the stop signal when their time is up, so every stage gets fresh
+        // ones. Events still queued from the last stage are applied first.
+        self.handle_stress_events();
+        self.stress_test = self.stress_test.next_run();
+        self.stress_events = Some(self.stress_test.subscribe());
+        self.memtest = MemTest::new();
+        let clock = self
+            .start_time
+            .map_or(0.0, |start| start.elapsed().as_secs_f64());
+        let Some(run) = &mut self.plan else {
+            return;
+        };
+        let Some(stage) = run.plan.stages.get(run.current) else {
+            return;
+        };
+        run.stage_start = clock;
+        self.stage_handle = Some(stage.start(&self.stress_test, &self.memtest));
+        // Idle and memory stages have no throughput of their own
+        self.last_sample = None;
+    }
+
+    /// Records the stage that just ended, then starts the next one or, after the last
+    /// stage, a thermal abort or a stop, saves the combined report.
+    fn finish_stage(&mut self, outcome: Result<StageOutcome, String>) {
+        let clock = self
+            .start_time
+            .map_or(0.0, |start| start.elapsed().as_secs_f64());
+        let Some(run) = &mut self.plan else {
+            return;
+        };
+        let Some(stage) = run.plan.stages.get(run.current) else {
+            return;
+        };
+        let mut report = StageReport::new(
+            stage,
+            run.stage_start,
+            clock,
+            outcome,
+            self.run_samples.as_slice(),
+        );
+        if run.stopped {
+            report.stop();
+        }
+        let aborted = report.outcome.starts_with("aborted");
+        run.reports.push(report);
+        run.current += 1;
+        if aborted || run.stopped || run.stage().is_none() {
+            self.finish_plan();
+        } else {
+            self.start_stage();
+        }
+    }
+
+    /// Ends the running stage early and skips the rest of the plan.
+    fn stop_plan(&mut self) {
+        if let Some(run) = &mut self.plan {
+            run.stopped = true;
+        }
+        self.stress_test.stop_signal.store(true, Ordering::Relaxed);
+        self.memtest.stop_signal.store(true, Ordering::Relaxed);
+    }
+
+    fn finish_plan(&mut self) {
+        let Some(run) = self.plan.take() else {
+            return;
+        };
+        let report = run.finish(self.system_usage.hardware_info(), self.run_samples.take());
+        self.saved_plan_report = Some(report.save(Path::new(PLANS_DIR)));
+        self.plan_report = Some(report);
+        self.mode = Mode::PlanReport;
+    }
+}
+
 fn avg_percent_usage_cpu(cpus: &Vec<CpuUsage>) -> f64 {
     let mut acc: f64 = 0.;
     for i in cpus {
@@ -179,8 +765,13 @@ fn ui_input_mode(frame: &mut Frame, app: &mut App) {
             Constraint::Length(3), // Duration Input
             Constraint::Length(3), // Time Unit Selection
             Constraint::Length(3), // CPU Count Selection
+            Constraint::Length(3), // CPU Affinity
+            Constraint::Length(3), // Target Load
+            Constraint::Length(3), // Load Profile
+            Constraint::Length(3), // Thermal Limit
+            Constraint::Length(3), // Workload Selection
             Constraint::Length(3), // OK Button (New)
-            Constraint::Length(3), // Instructions
+            Constraint::Length(app.form_errors.len().max(1) as u16 + 2), // Instructions or errors
             Constraint::Min(0),    // Remaining space
         ])
         .split(size);
@@ -196,7 +787,7 @@ fn ui_input_mode(frame: &mut Frame, app: &mut App) {
 
     let input_block_style = if matches!(app.current_input_focus, InputFocusElement::ValueInput) {
         Style::default()
-            .fg(Color::Cyan)
+            .fg(app.theme.accent)
             .add_modifier(Modifier::BOLD)
     } else {
         Style::default().fg(Color::Reset)
@@ -205,14 +796,14 @@ fn ui_input_mode(frame: &mut Frame, app: &mut App) {
     // Time unit selection
     let seconds_style = if matches!(app.selected_unit, TimeUnit::Seconds) {
         Style::default()
-            .fg(Color::Green)
+            .fg(app.theme.selected)
             .add_modifier(Modifier::BOLD)
     } else {
         Style::default().fg(Color::Reset)
     };
     let minutes_style = if matches!(app.selected_unit, TimeUnit::Minutes) {
         Style::default()
-            .fg(Color::Green)
+            .fg(app.theme.selected)
             .add_modifier(Modifier::BOLD)
     } else {
         Style::default().fg(Color::Reset)
@@ -250,27 +841,21 @@ fn ui_input_mode(frame: &mut Frame, app: &mut App) {
     frame.render_widget(input_paragraph, chunks[2]);
 
     // CPU Count Selection
-    let cpu_count_block_style = if matches!(
+    let cpu_count_block_style = if app
+        .selected_cpu_count
+        .parse::<usize>()
+        .is_ok_and(|cores| cores == 0 || cores > app.total_logical_cores)
+    {
+        Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+    } else if matches!(
         app.current_input_focus,
         InputFocusElement::CpuCountSelection
     ) {
-        if app.selected_cpu_count.len() > 0
-            && app.selected_cpu_count.parse::<usize>().unwrap() > app.total_logical_cores
-        {
-            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
-        } else {
-            Style::default()
-                .fg(Color::Cyan)
-                .add_modifier(Modifier::BOLD)
-        }
+        Style::default()
+            .fg(app.theme.accent)
+            .add_modifier(Modifier::BOLD)
     } else {
-        if app.selected_cpu_count.len() > 0
-            && app.selected_cpu_count.parse::<usize>().unwrap() > app.total_logical_cores
-        {
-            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
-        } else {
-            Style::default().fg(Color::Reset)
-        }
+        Style::default().fg(Color::Reset)
     };
 
     let cpu_count_block = Block::default().borders(Borders::ALL).title(format!(
@@ -283,28 +868,138 @@ fn ui_input_mode(frame: &mut Frame, app: &mut App) {
         .block(cpu_count_block);
     frame.render_widget(cpu_count_paragraph, chunks[3]); // Adjusted chunk index
 
+    // CPU Affinity
+    let affinity_style = if matches!(app.current_input_focus, InputFocusElement::AffinityInput) {
+        Style::default()
+            .fg(app.theme.accent)
+            .add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().fg(Color::Reset)
+    };
+    let affinity_paragraph = Paragraph::new(app.selected_affinity.as_str())
+        .style(affinity_style)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Pin to CPUs (e.g. 0,2,4-7 or 'physical', empty = unpinned)"),
+        );
+    frame.render_widget(affinity_paragraph, chunks[4]);
+
+    // Target Load
+    let target_load_style = if matches!(app.current_input_focus, InputFocusElement::TargetLoadInput)
+    {
+        Style::default()
+            .fg(app.theme.accent)
+            .add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().fg(Color::Reset)
+    };
+    let target_load_paragraph = Paragraph::new(app.selected_target_load.as_str())
+        .style(target_load_style)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Target Load % (1-100, empty = full load)"),
+        );
+    frame.render_widget(target_load_paragraph, chunks[5]);
+
+    // Load Profile
+    let profile_style = if matches!(app.current_input_focus, InputFocusElement::ProfileInput) {
+        Style::default()
+            .fg(app.theme.accent)
+            .add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().fg(Color::Reset)
+    };
+    let profile_paragraph = Paragraph::new(app.selected_profile.as_str())
+        .style(profile_style)
+        .block(Block::default().borders(Borders::ALL).title(
+            "Load Profile (e.g. 'ramp 0 100 60s; hold 100 5m; square 100 0 10s 10s 2m @4'), overrides duration and target",
+        ));
+    frame.render_widget(profile_paragraph, chunks[6]);
+
+    // Thermal Limit
+    let thermal_style = if matches!(
+        app.current_input_focus,
+        InputFocusElement::ThermalLimitInput
+    ) {
+        Style::default()
+            .fg(app.theme.accent)
+            .add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().fg(Color::Reset)
+    };
+    let thermal_paragraph = Paragraph::new(app.selected_thermal_limit.as_str())
+        .style(thermal_style)
+        .block(Block::default().borders(Borders::ALL).title(
+            "Thermal Limit °C (e.g. '95' or '95, 85 for 30s' to also abort after 30s above 85, empty = none)",
+        ));
+    frame.render_widget(thermal_paragraph, chunks[7]);
+
+    // Workload Selection
+    let workload_style = if matches!(
+        app.current_input_focus,
+        InputFocusElement::WorkloadSelection
+    ) {
+        Style::default()
+            .fg(app.theme.selected)
+            .add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().fg(Color::Reset)
+    };
+    let workload_names = app.stress_test.workloads().names();
+    let mut workload_spans = vec![];
+    for (i, name) in workload_names.iter().enumerate() {
+        if i > 0 {
+            workload_spans.push(Span::raw("    "));
+        }
+        if i == app.selected_workload % workload_names.len() {
+            workload_spans.push(Span::styled(format!("  {}  ", name), workload_style));
+        } else {
+            workload_spans.push(Span::raw(format!("  {}  ", name)));
+        }
+    }
+    let workload_paragraph = Paragraph::new(Line::from(workload_spans)).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Select Workload"),
+    );
+    frame.render_widget(workload_paragraph, chunks[8]);
+
     // OK Button
     let ok_button_style = if matches!(app.current_input_focus, InputFocusElement::OkButton) {
         Style::default()
-            .fg(Color::Green)
+            .fg(app.theme.selected)
             .add_modifier(Modifier::BOLD)
             .add_modifier(Modifier::REVERSED)
     } else {
-        Style::default().fg(Color::White)
+        Style::default().fg(app.theme.text)
     };
     let ok_button = Paragraph::new("    OK    ")
         .style(ok_button_style)
         .alignment(ratatui::layout::Alignment::Center)
         .block(Block::default().borders(Borders::ALL));
-    frame.render_widget(ok_button, chunks[4]); // Adjusted chunk index
+    frame.render_widget(ok_button, chunks[9]); // Adjusted chunk index
 
-    // Instructions
-    let instructions_block = Block::default().borders(Borders::ALL).title("Instructions");
-    let instructions_paragraph = Paragraph::new(
-        "Type duration, TAB to cycle focus. Up/Down/Left/Right to select and change values. Up/Down for Cores. ENTER on OK to start. 'q' or 'Q' to quit.",
-    )
-    .block(instructions_block);
-    frame.render_widget(instructions_paragraph, chunks[5]); // Adjusted chunk index
+    // Instructions, or what kept the last attempt from starting
+    if app.form_errors.is_empty() {
+        let instructions_block = Block::default().borders(Borders::ALL).title("Instructions");
+        let instructions_paragraph = Paragraph::new(
+            "Type duration, TAB to cycle focus. Up/Down/Left/Right to select and change values. Up/Down for Cores and Workload. ENTER on OK to start. F2 for saved runs. F3 for a core-scaling sweep, the duration being per step. 'q' or 'Q' to quit.",
+        )
+        .block(instructions_block);
+        frame.render_widget(instructions_paragraph, chunks[10]); // Adjusted chunk index
+    } else {
+        let errors: Vec<Line> = app
+            .form_errors
+            .iter()
+            .map(|error| Line::from(error.as_str()))
+            .collect();
+        let errors_paragraph = Paragraph::new(errors)
+            .style(Style::default().fg(Color::Red))
+            .block(Block::default().borders(Borders::ALL).title("Cannot start"));
+        frame.render_widget(errors_paragraph, chunks[10]);
+    }
 
     // Position the cursor in the input field if it's focused
     if matches!(app.current_input_focus, InputFocusElement::ValueInput) {
@@ -323,6 +1018,37 @@ fn ui_input_mode(frame: &mut Frame, app: &mut App) {
             y: chunks[0].y + 8,
         });
     }
+
+    if matches!(app.current_input_focus, InputFocusElement::AffinityInput) {
+        frame.set_cursor_position(Position {
+            x: chunks[4].x + app.selected_affinity.len() as u16 + 1,
+            y: chunks[4].y + 1,
+        });
+    }
+
+    if matches!(app.current_input_focus, InputFocusElement::TargetLoadInput) {
+        frame.set_cursor_position(Position {
+            x: chunks[5].x + app.selected_target_load.len() as u16 + 1,
+            y: chunks[5].y + 1,
+        });
+    }
+
+    if matches!(app.current_input_focus, InputFocusElement::ProfileInput) {
+        frame.set_cursor_position(Position {
+            x: chunks[6].x + app.selected_profile.len() as u16 + 1,
+            y: chunks[6].y + 1,
+        });
+    }
+
+    if matches!(
+        app.current_input_focus,
+        InputFocusElement::ThermalLimitInput
+    ) {
+        frame.set_cursor_position(Position {
+            x: chunks[7].x + app.selected_thermal_limit.len() as u16 + 1,
+            y: chunks[7].y + 1,
+        });
+    }
 }
 
 /// Draws the application UI in the chart mode.
@@ -334,93 +1060,577 @@ fn ui_chart_mode(frame: &mut Frame, app: &mut App) {
         .direction(Direction::Horizontal)
         .constraints([Constraint::Ratio(3, 4), Constraint::Ratio(1, 4)])
         .split(size);
+    // Usage and temperature on top, then throughput and frequency on their own axes
+    let charts = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Ratio(2, 5),
+            Constraint::Ratio(2, 5),
+            Constraint::Ratio(1, 5),
+        ])
+        .split(chunks[0]);
 
     // Chart Block
-    let chart_block = Block::default()
-        .title(Line::from(vec![
+    let mut title = match (&app.reviewing, &app.plan) {
+        (Some(record), _) => vec![
+            Span::styled(
+                format!("Saved Run {}", start_time(record)),
+                Style::default()
+                    .fg(app.theme.accent)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(format!(
+                " ({}, read-only) Esc: back",
+                format_duration(record.elapsed_secs)
+            )),
+        ],
+        (None, Some(run)) => vec![
+            Span::styled(
+                format!("Plan {}", run.plan.name),
+                Style::default()
+                    .fg(app.theme.accent)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(format!(
+                " stage {}/{}: {} (Elapsed: {}s / {}s) Esc: stop",
+                run.current + 1,
+                run.plan.stages.len(),
+                run.stage().map_or("", |stage| stage.name.as_str()),
+                app.elapsed_secs,
+                app.total_duration_secs
+            )),
+        ],
+        (None, None) => vec![
             Span::styled(
                 "Timer Chart",
                 Style::default()
-                    .fg(Color::Cyan)
+                    .fg(app.theme.accent)
                     .add_modifier(Modifier::BOLD),
             ),
             Span::raw(format!(
                 " (Elapsed: {}s / {}s)",
                 app.elapsed_secs, app.total_duration_secs
             )),
-        ]))
+            if app.stress_test.pause.is_paused() {
+                Span::styled(
+                    " PAUSED - P to resume",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                )
+            } else {
+                Span::raw(" P to pause")
+            },
+        ],
+    };
+    title.push(Span::raw(format!(" V: view ({})", app.usage_view.name())));
+    title.push(Span::raw(format!(
+        " +/-: zoom ({}x) Left/Right: scroll{}",
+        app.viewport.zoom,
+        if app.viewport.is_live() {
+            ""
+        } else {
+            " Home: live"
+        }
+    )));
+    let chart_block = Block::default()
+        .title(Line::from(title))
         .borders(Borders::ALL);
 
-    // Calculate max x and max y for chart scaling
-    let max_x = app.total_duration_secs as f64;
-    let max_y = 100.0; // Assuming chart values won't exceed 30 much based on our sine example
+    // Calculate the visible time window and max y for chart scaling
+    let (x_start, x_end) = app.chart_window();
+    let max_points = charts[0].width as usize * 2;
+    // Usage and temperature share the axis, which grows for sensors above 100 °C
+    let hottest_seen = app.temperature_data.max().unwrap_or(0.0);
+    let max_y = f64::max(100.0, (hottest_seen / 10.0).ceil() * 10.0);
 
-    let datasets = vec![
-        Dataset::default()
-            .name("Value over time")
-            .marker(symbols::Marker::Dot)
-            .style(Style::default().fg(Color::Green))
-            .graph_type(GraphType::Line)
-            .data(&app.chart_data),
-    ];
+    if matches!(app.usage_view, UsageView::Heatmap) {
+        render_heatmap(
+            frame,
+            &app.core_usage_data,
+            usage_color,
+            app.chart_window(),
+            chart_block,
+            charts[0],
+        );
+        render_throughput_chart(frame, app, charts[1]);
+        render_frequency_chart(frame, app, charts[2]);
+        render_side_panel(frame, app, chunks[1]);
+        return;
+    }
 
-    let chart = Chart::new(datasets)
-        .block(chart_block)
+    let core_points: Vec<Vec<(f64, f64)>> = match app.usage_view {
+        UsageView::PerCore => app
+            .core_usage_data
+            .iter()
+            .map(|history| history.points(x_start, x_end, max_points))
+            .collect(),
+        _ => Vec::new(),
+    };
+    let usage_points = app.chart_data.points(x_start, x_end, max_points);
+    let temperature_points = app.temperature_data.points(x_start, x_end, max_points);
+
+    let mut datasets = Vec::new();
+    if matches!(app.usage_view, UsageView::PerCore) {
+        for (i, history) in core_points.iter().enumerate() {
+            datasets.push(
+                Dataset::default()
+                    .name(format!("CPU {}", i))
+                    .marker(symbols::Marker::Braille)
+                    .style(Style::default().fg(CPU_COLORS[i % CPU_COLORS.len()]))
+                    .graph_type(GraphType::Line)
+                    .data(history),
+            );
+        }
+    } else {
+        datasets.push(
+            Dataset::default()
+                .name("Value over time")
+                .marker(symbols::Marker::Dot)
+                .style(Style::default().fg(Color::Green))
+                .graph_type(GraphType::Line)
+                .data(&usage_points),
+        );
+    }
+    if !app.temperature_data.is_empty() {
+        datasets.push(
+            Dataset::default()
+                .name("Hottest sensor °C")
+                .marker(symbols::Marker::Dot)
+                .style(Style::default().fg(Color::Red))
+                .graph_type(GraphType::Line)
+                .data(&temperature_points),
+        );
+    }
+    if !app.profile_points.is_empty() {
+        datasets.push(
+            Dataset::default()
+                .name("Planned load")
+                .marker(symbols::Marker::Braille)
+                .style(Style::default().fg(Color::Yellow))
+                .graph_type(GraphType::Line)
+                .data(&app.profile_points),
+        );
+    }
+
+    let mut chart = Chart::new(datasets).block(chart_block);
+    if matches!(app.usage_view, UsageView::PerCore) {
+        // One legend entry per core would cover the chart on big machines
+        chart = chart.legend_position(None);
+    }
+    let chart = chart
         .x_axis(
             Axis::default()
                 .title("Time (s)")
                 .style(Style::default().fg(Color::Gray))
-                .bounds([0.0, max_x])
+                .bounds([x_start, x_end])
                 .labels(vec![
-                    Span::styled("0", Style::default().fg(Color::White)),
                     Span::styled(
-                        format!("{}", max_x / 2.0),
-                        Style::default().fg(Color::White),
+                        format!("{:.0}", x_start),
+                        Style::default().fg(app.theme.text),
                     ),
-                    Span::styled(format!("{}", max_x), Style::default().fg(Color::White)),
+                    Span::styled(
+                        format!("{:.0}", (x_start + x_end) / 2.0),
+                        Style::default().fg(app.theme.text),
+                    ),
+                    Span::styled(format!("{:.0}", x_end), Style::default().fg(app.theme.text)),
                 ]),
         )
         .y_axis(
             Axis::default()
-                .title("Usage %")
+                .title("Usage % / °C")
                 .style(Style::default().fg(Color::Gray))
                 .bounds([0.0, max_y])
                 .labels(vec![
-                    Span::styled("0", Style::default().fg(Color::White)),
+                    Span::styled("0", Style::default().fg(app.theme.text)),
                     Span::styled(
                         format!("{}", max_y / 2.0),
-                        Style::default().fg(Color::White),
+                        Style::default().fg(app.theme.text),
                     ),
-                    Span::styled(format!("{}", max_y), Style::default().fg(Color::White)),
+                    Span::styled(format!("{}", max_y), Style::default().fg(app.theme.text)),
                 ]),
         );
-    frame.render_widget(chart, chunks[0]);
+    frame.render_widget(chart, charts[0]);
+    render_throughput_chart(frame, app, charts[1]);
+    render_frequency_chart(frame, app, charts[2]);
+    render_side_panel(frame, app, chunks[1]);
+}
 
-    // System Info Block
-    let (used_ram, total_ram) = app.system_usage.get_ram_info(); // Get fresh RAM info
+/// Colours cycled through for per-core values.
+const CPU_COLORS: [Color; 8] = [
+    Color::LightRed,
+    Color::LightGreen,
+    Color::LightBlue,
+    Color::LightCyan,
+    Color::LightMagenta,
+    Color::Yellow,
+    Color::Green,
+    Color::Blue,
+];
 
-    let mut system_info_text = Vec::new();
+/// Blue when idle through green to red at full load.
+fn usage_color(usage: f64) -> Color {
+    let usage = usage.clamp(0.0, 100.0);
+    Color::Rgb(
+        (usage * 2.55) as u8,
+        (255.0 - (usage - 50.0).abs() * 5.1).max(0.0) as u8,
+        (255.0 - usage * 5.1).max(0.0) as u8,
+    )
+}
 
-    // RAM Usage on top
-    system_info_text.push(Line::from(format!(
-        "RAM Usage: {} MB / {} MB",
-        used_ram / 1024 / 1024,
-        total_ram / 1024 / 1024
-    )));
-    system_info_text.push(Line::from("")); // Spacer
+/// Mean value of `points` in each of `columns` equal time slices of [start, end].
+/// Slices without points repeat the previous value, up to the last point.
+fn bucket_values(points: &[(f64, f64)], columns: usize, start: f64, end: f64) -> Vec<Option<f64>> {
+    let mut sums = vec![(0.0, 0u32); columns];
+    let width = (end - start).max(f64::EPSILON) / columns.max(1) as f64;
+    for &(t, value) in points {
+        if t < start || t > end {
+            continue;
+        }
+        let column = (((t - start) / width) as usize).min(columns.saturating_sub(1));
+        sums[column].0 += value;
+        sums[column].1 += 1;
+    }
+    let last_t = points.last().map_or(start, |&(t, _)| t);
+    let mut previous = None;
+    sums.iter()
+        .enumerate()
+        .map(|(column, &(sum, count))| {
+            if count > 0 {
+                previous = Some(sum / count as f64);
+            } else if start + column as f64 * width > last_t {
+                previous = None;
+            }
+            previous
+        })
+        .collect()
+}
 
-    // CPU Info (2 items per line with different colors, limited by selected_cpu_count)
+/// Draws a time × core grid of per-core values, two cores per text row.
+fn render_heatmap(
+    frame: &mut Frame,
+    series: &[TimeSeries],
+    color: impl Fn(f64) -> Color,
+    (x_start, x_end): (f64, f64),
+    block: Block,
+    area: Rect,
+) {
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+    let cores = series.len();
+    if cores == 0 || inner.height == 0 {
+        return;
+    }
+
+    let label_width = format!("{:>3} ", cores - 1).len();
+    let columns = (inner.width as usize).saturating_sub(label_width).max(1);
+    // Fold neighbouring cores together when they do not fit two to a row
+    let cores_per_cell = cores.div_ceil(inner.height as usize * 2).max(1);
+    let cells: Vec<(usize, Vec<Option<f64>>)> = series
+        .chunks(cores_per_cell)
+        .enumerate()
+        .map(|(i, group)| {
+            let buckets: Vec<Vec<Option<f64>>> = group
+                .iter()
+                .map(|history| {
+                    let points = history.points(x_start, x_end, columns * 2);
+                    bucket_values(&points, columns, x_start, x_end)
+                })
+                .collect();
+            let merged = (0..columns)
+                .map(|c| {
+                    let values: Vec<f64> = buckets.iter().filter_map(|b| b[c]).collect();
+                    (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+                })
+                .collect();
+            (i * cores_per_cell, merged)
+        })
+        .collect();
+
+    let mut lines = Vec::new();
+    for pair in cells.chunks(2) {
+        let (first_core, top) = &pair[0];
+        let bottom = pair.get(1).map(|(_, values)| values);
+        let mut spans = vec![Span::styled(
+            format!("{:>3} ", first_core),
+            Style::default().fg(Color::Gray),
+        )];
+        for column in 0..columns {
+            let top = top[column];
+            let bottom = bottom.and_then(|values| values[column]);
+            let span = match (top, bottom) {
+                (Some(top), bottom) => Span::styled(
+                    "▀",
+                    Style::default()
+                        .fg(color(top))
+                        .bg(bottom.map_or(Color::Reset, &color)),
+                ),
+                (None, Some(bottom)) => Span::styled("▄", Style::default().fg(color(bottom))),
+                (None, None) => Span::raw(" "),
+            };
+            spans.push(span);
+        }
+        lines.push(Line::from(spans));
+    }
+    frame.render_widget(Paragraph::new(lines), inner);
+}
+
+/// Draws the live system info, or the summary of the saved run being reviewed.
+fn render_side_panel(frame: &mut Frame, app: &mut App, area: Rect) {
+    if let Some(record) = &app.reviewing {
+        render_record_info(frame, record, app.theme, area);
+    } else if let Some(run) = &app.plan {
+        let stages_height = (run.plan.stages.len() as u16 + 2).min(area.height / 2);
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(stages_height), Constraint::Min(0)])
+            .split(area);
+        render_plan_stages(frame, run, app.theme, chunks[0]);
+        render_system_info(frame, app, chunks[1]);
+    } else {
+        render_system_info(frame, app, area);
+    }
+}
+
+/// Lists the stages of the running plan: finished ones with their outcome, the
+/// running one highlighted and the ones still to come.
+fn render_plan_stages(frame: &mut Frame, run: &PlanRun, theme: Theme, area: Rect) {
+    let lines: Vec<Line> = run
+        .plan
+        .stages
+        .iter()
+        .enumerate()
+        .map(|(i, stage)| match run.reports.get(i) {
+            Some(report) => Line::styled(
+                format!(
+                    "{} {} ({})",
+                    if report.passed() { "✓" } else { "✗" },
+                    stage.name,
+                    report.outcome
+                ),
+                Style::default().fg(if report.passed() {
+                    Color::Green
+                } else {
+                    Color::Red
+                }),
+            ),
+            None if i == run.current => Line::styled(
+                format!(
+                    "▶ {} ({})",
+                    stage.name,
+                    format_duration(stage.duration_secs as f64)
+                ),
+                Style::default()
+                    .fg(theme.accent)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            None => Line::from(format!(
+                "  {} ({})",
+                stage.name,
+                format_duration(stage.duration_secs as f64)
+            )),
+        })
+        .collect();
+    // Keep the running stage in view on long plans
+    let visible = area.height.saturating_sub(2) as usize;
+    let scroll = (run.current + 1).saturating_sub(visible) as u16;
+    let block = Block::default()
+        .title(format!(
+            "Stages ({}/{})",
+            run.current + 1,
+            run.plan.stages.len()
+        ))
+        .borders(Borders::ALL)
+        .style(Style::default().bg(theme.background));
+    frame.render_widget(Paragraph::new(lines).block(block).scroll((scroll, 0)), area);
+}
+
+/// Draws what was saved about a run: setup, machine and results.
+fn render_record_info(frame: &mut Frame, record: &RunRecord, theme: Theme, area: Rect) {
+    let outcome_style = if record.outcome == "passed" {
+        Style::default()
+            .fg(Color::Green)
+            .add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+    };
+    let settings = &record.settings;
+    let mut lines = vec![
+        Line::styled(record.outcome.to_uppercase(), outcome_style),
+        Line::from(format!(
+            "Score: {} ({:.2} {})",
+            record.score, record.rate, record.rate_unit
+        )),
+        Line::from(""),
+        Line::from(format!("Workload: {}", settings.workload)),
+        Line::from(format!(
+            "Threads: {} ({})",
+            settings.threads, settings.affinity
+        )),
+        Line::from(format!(
+            "Duration: {} of {}",
+            format_duration(record.elapsed_secs),
+            format_duration(settings.duration_secs as f64)
+        )),
+    ];
+    if let Some(target) = settings.target_load {
+        lines.push(Line::from(format!("Target load: {:.0}%", target)));
+    }
+    if let Some(profile) = &settings.profile {
+        lines.push(Line::from(format!("Profile: {}", profile)));
+    }
+    if let Some(limit) = &settings.thermal_limit {
+        lines.push(Line::from(format!("Thermal limit: {}", limit)));
+    }
+    lines.push(Line::from(""));
+
+    let machine = &record.machine;
+    lines.push(Line::from(format!("Machine: {}", machine.hostname)));
+    lines.push(Line::from(format!("CPU: {}", machine.cpu_brand)));
+    lines.push(Line::from(format!(
+        "Cores: {} logical, {} physical",
+        machine.logical_cores,
+        machine
+            .physical_cores
+            .map_or("?".to_string(), |cores| cores.to_string())
+    )));
+    lines.push(Line::from(format!(
+        "RAM: {} MB",
+        machine.total_memory / 1024 / 1024
+    )));
+    lines.push(Line::from(format!("OS: {}", machine.os)));
+    lines.push(Line::from(""));
+
+    if let Some(abort) = &record.thermal_abort {
+        lines.push(Line::styled(
+            format!("Thermal abort: {}", abort),
+            Style::default().fg(Color::Red),
+        ));
+    }
+    if let Some(peak) = record.peak_temperature {
+        lines.push(Line::from(format!("Peak temperature: {:.1}°C", peak)));
+    }
+    let throttling = &record.throttling;
+    if throttling.throttled_secs > 0.0 {
+        lines.push(Line::styled(
+            format!(
+                "Throttled for {:.0}s ({} clock drops, {} rate drops)",
+                throttling.throttled_secs, throttling.frequency_drops, throttling.rate_drops
+            ),
+            Style::default().fg(Color::Yellow),
+        ));
+    } else if let Some(frequency) = throttling.average_frequency {
+        lines.push(Line::from(format!(
+            "No throttling, {:.0} MHz average",
+            frequency
+        )));
+    }
+    if !record.errors.is_empty() {
+        lines.push(Line::styled(
+            format!("Errors: {} wrong results", record.errors.len()),
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        ));
+    }
+    lines.push(Line::from(""));
+
+    lines.push(Line::from("Workers:"));
+    for worker in &record.workers {
+        lines.push(Line::from(format!(
+            "  #{} on CPU {}: {} ({:.2} {})",
+            worker.thread_index,
+            worker.core.map_or("?".to_string(), |core| core.to_string()),
+            worker.score,
+            worker.rate,
+            record.rate_unit
+        )));
+    }
+
+    let block = Block::default()
+        .title("Saved Run")
+        .borders(Borders::ALL)
+        .style(Style::default().bg(theme.background));
+    frame.render_widget(Paragraph::new(Text::from(lines)).block(block), area);
+}
+
+/// Draws the side panel with RAM, temperatures, live throughput and per-core usage.
+fn render_system_info(frame: &mut Frame, app: &mut App, area: Rect) {
+    // System Info Block
+    let (used_ram, total_ram) = app.system_usage.get_ram_info(); // Get fresh RAM info
+
+    let mut system_info_text = Vec::new();
+
+    // RAM Usage on top
+    system_info_text.push(Line::from(format!(
+        "RAM Usage: {} MB / {} MB",
+        used_ram / 1024 / 1024,
+        total_ram / 1024 / 1024
+    )));
+    system_info_text.push(Line::from("")); // Spacer
+
+    // Temperatures, hottest first
+    let mut temperatures: Vec<&Temperature> = app
+        .temperatures_cached
+        .iter()
+        .filter(|t| t.current.is_some())
+        .collect();
+    temperatures.sort_by(|a, b| b.current.unwrap().total_cmp(&a.current.unwrap()));
+    system_info_text.push(Line::from("Temperatures:"));
+    if temperatures.is_empty() {
+        system_info_text.push(Line::from("  no sensors found"));
+    }
+    for temperature in temperatures {
+        let current = temperature.current.unwrap();
+        let near_critical = temperature
+            .critical
+            .is_some_and(|critical| current >= critical - 10.0);
+        let style = if near_critical {
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::Reset)
+        };
+        let mut text = format!("  {}: {:.1}°C", temperature.label, current);
+        if let Some(max) = temperature.max {
+            text.push_str(&format!(" max {:.0}", max));
+        }
+        if let Some(critical) = temperature.critical {
+            text.push_str(&format!(" crit {:.0}", critical));
+        }
+        system_info_text.push(Line::from(Span::styled(text, style)));
+    }
+    system_info_text.push(Line::from("")); // Spacer
+
+    // Live throughput from the stress test's progress events
+    if let Some(sample) = &app.last_sample {
+        system_info_text.push(Line::from(format!(
+            "Throughput: {:.2} {} ({} workers)",
+            sample.rate,
+            app.rate_unit,
+            sample.workers.len()
+        )));
+    } else {
+        system_info_text.push(Line::from("Throughput: waiting for first sample"));
+    }
+    if let Some(sample) = &app.last_sample
+        && let Some(target) = sample.target_load
+    {
+        system_info_text.push(Line::from(format!(
+            "Target load: {:.0}% on {} workers (duty cycle {:.0}%)",
+            target,
+            sample.active_threads,
+            sample.duty * 100.0
+        )));
+    }
+    if !app.run_errors.is_empty() {
+        system_info_text.push(Line::from(Span::styled(
+            format!("Errors: {} wrong results", app.run_errors.len()),
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        )));
+    }
+    system_info_text.push(Line::from("")); // Spacer
+
+    // CPU Info (2 items per line with different colors, limited by selected_cpu_count)
     system_info_text.push(Line::from("CPU Usage:"));
-    let cpu_colors = [
-        Color::LightRed,
-        Color::LightGreen,
-        Color::LightBlue,
-        Color::LightCyan,
-        Color::LightMagenta,
-        Color::Yellow,
-        Color::Green,
-        Color::Blue,
-    ]; // Define a set of colors
+    let cpu_colors = CPU_COLORS;
 
     for i in 0..app.cpu_info_cached.len() {
         if let Some(cpu) = app.cpu_info_cached.get(i) {
@@ -451,11 +1661,648 @@ fn ui_chart_mode(frame: &mut Frame, app: &mut App) {
     let system_info_block = Block::default()
         .title("System Info")
         .borders(Borders::ALL)
-        .style(Style::default().bg(Color::Black));
+        .style(Style::default().bg(app.theme.background));
 
     let system_info_paragraph =
         Paragraph::new(Text::from(system_info_text)).block(system_info_block);
-    frame.render_widget(system_info_paragraph, chunks[1]);
+    frame.render_widget(system_info_paragraph, area);
+}
+
+/// Draws the work the stress workers got done in each sampling interval.
+fn render_throughput_chart(frame: &mut Frame, app: &App, area: Rect) {
+    let (x_start, x_end) = app.chart_window();
+    let points = app
+        .throughput_data
+        .points(x_start, x_end, area.width as usize * 2);
+    let highest = app.throughput_data.max().unwrap_or(0.0);
+    // Headroom above the peak so a flat line does not hug the border
+    let max_y = if highest > 0.0 { highest * 1.2 } else { 1.0 };
+
+    let datasets = vec![
+        Dataset::default()
+            .name(app.rate_unit.clone())
+            .marker(symbols::Marker::Braille)
+            .style(Style::default().fg(Color::Cyan))
+            .graph_type(GraphType::Line)
+            .data(&points),
+    ];
+    let title = match app.throughput_data.last() {
+        Some((_, rate)) => format!("Throughput ({:.2} {})", rate, app.rate_unit),
+        None => "Throughput".to_string(),
+    };
+    let chart = Chart::new(datasets)
+        .block(Block::default().title(title).borders(Borders::ALL))
+        .x_axis(
+            Axis::default()
+                .style(Style::default().fg(Color::Gray))
+                .bounds([x_start, x_end]),
+        )
+        .y_axis(
+            Axis::default()
+                .title(app.rate_unit.as_str())
+                .style(Style::default().fg(Color::Gray))
+                .bounds([0.0, max_y])
+                .labels(vec![
+                    Span::styled("0", Style::default().fg(app.theme.text)),
+                    Span::styled(
+                        format!("{:.1}", max_y / 2.0),
+                        Style::default().fg(app.theme.text),
+                    ),
+                    Span::styled(format!("{:.1}", max_y), Style::default().fg(app.theme.text)),
+                ]),
+        );
+    frame.render_widget(chart, area);
+}
+
+/// Draws the mean frequency of the workers' CPUs against the cpufreq limits, or
+/// every CPU's frequency when the usage chart shows cores.
+fn render_frequency_chart(frame: &mut Frame, app: &App, area: Rect) {
+    let (x_start, x_end) = app.chart_window();
+    let max_points = area.width as usize * 2;
+    let per_core =
+        !app.core_frequency_data.is_empty() && !matches!(app.usage_view, UsageView::Average);
+    let points = app.frequency_data.points(x_start, x_end, max_points);
+    // A saved run only knows the upper limit of the machine it ran on
+    let (min_limit, max_limit) = match &app.reviewing {
+        Some(record) => (None, record.machine.max_frequency),
+        None => (
+            app.cpu_info_cached
+                .iter()
+                .filter_map(|cpu| cpu.min_frequency)
+                .min(),
+            app.cpu_info_cached
+                .iter()
+                .filter_map(|cpu| cpu.max_frequency)
+                .max(),
+        ),
+    };
+    let highest_seen = if per_core {
+        app.core_frequency_data
+            .iter()
+            .filter_map(TimeSeries::max)
+            .fold(0.0, f64::max)
+    } else {
+        app.frequency_data.max().unwrap_or(0.0)
+    };
+    let max_y =
+        (f64::max(highest_seen, max_limit.unwrap_or(0) as f64) * 1.1 / 500.0).ceil() * 500.0;
+    let max_y = max_y.max(500.0);
+
+    let title = match app.frequency_data.last() {
+        Some((_, frequency)) if per_core => {
+            format!("Frequency per core ({:.0} MHz mean)", frequency)
+        }
+        Some((_, frequency)) => format!("Frequency ({:.0} MHz)", frequency),
+        None => "Frequency (not reported)".to_string(),
+    };
+    let block = Block::default().title(title).borders(Borders::ALL);
+    if per_core && matches!(app.usage_view, UsageView::Heatmap) {
+        // Coloured like usage, from 0 MHz up to the top of the line chart
+        render_heatmap(
+            frame,
+            &app.core_frequency_data,
+            |frequency| usage_color(frequency / max_y * 100.0),
+            (x_start, x_end),
+            block,
+            area,
+        );
+        return;
+    }
+    let core_points: Vec<Vec<(f64, f64)>> = if per_core {
+        app.core_frequency_data
+            .iter()
+            .map(|history| history.points(x_start, x_end, max_points))
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let min_line: Vec<(f64, f64)> = min_limit
+        .map(|f| vec![(x_start, f as f64), (x_end, f as f64)])
+        .unwrap_or_default();
+    let max_line: Vec<(f64, f64)> = max_limit
+        .map(|f| vec![(x_start, f as f64), (x_end, f as f64)])
+        .unwrap_or_default();
+
+    let mut datasets = Vec::new();
+    if per_core {
+        for (i, history) in core_points.iter().enumerate() {
+            datasets.push(
+                Dataset::default()
+                    .name(format!("CPU {}", i))
+                    .marker(symbols::Marker::Braille)
+                    .style(Style::default().fg(CPU_COLORS[i % CPU_COLORS.len()]))
+                    .graph_type(GraphType::Line)
+                    .data(history),
+            );
+        }
+    } else {
+        datasets.push(
+            Dataset::default()
+                .name("Mean MHz")
+                .marker(symbols::Marker::Braille)
+                .style(Style::default().fg(Color::Magenta))
+                .graph_type(GraphType::Line)
+                .data(&points),
+        );
+    }
+    if !max_line.is_empty() {
+        datasets.push(
+            Dataset::default()
+                .name("cpufreq max")
+                .marker(symbols::Marker::Dot)
+                .style(Style::default().fg(Color::DarkGray))
+                .graph_type(GraphType::Line)
+                .data(&max_line),
+        );
+    }
+    if !min_line.is_empty() {
+        datasets.push(
+            Dataset::default()
+                .name("cpufreq min")
+                .marker(symbols::Marker::Dot)
+                .style(Style::default().fg(Color::DarkGray))
+                .graph_type(GraphType::Line)
+                .data(&min_line),
+        );
+    }
+
+    let mut chart = Chart::new(datasets).block(block);
+    if per_core {
+        chart = chart.legend_position(None);
+    }
+    let chart = chart
+        .x_axis(
+            Axis::default()
+                .style(Style::default().fg(Color::Gray))
+                .bounds([x_start, x_end]),
+        )
+        .y_axis(
+            Axis::default()
+                .title("MHz")
+                .style(Style::default().fg(Color::Gray))
+                .bounds([0.0, max_y])
+                .labels(vec![
+                    Span::styled("0", Style::default().fg(app.theme.text)),
+                    Span::styled(
+                        format!("{}", max_y / 2.0),
+                        Style::default().fg(app.theme.text),
+                    ),
+                    Span::styled(format!("{}", max_y), Style::default().fg(app.theme.text)),
+                ]),
+        );
+    frame.render_widget(chart, area);
+}
+
+/// Draws the list of saved runs.
+fn ui_history_mode(frame: &mut Frame, app: &mut App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(3)])
+        .split(frame.area());
+
+    let header = Row::new([
+        "Date", "Machine", "Workload", "Cores", "Duration", "Score", "Outcome",
+    ])
+    .style(
+        Style::default()
+            .fg(Color::Yellow)
+            .add_modifier(Modifier::BOLD),
+    );
+    let visible = app.history.visible();
+    let rows: Vec<Row> = visible
+        .iter()
+        .map(|(path, record)| {
+            let outcome_color = if record.outcome == "passed" {
+                Color::Green
+            } else {
+                Color::Red
+            };
+            let date = if app.history.marked.as_ref() == Some(path) {
+                Cell::from(format!("{} *", start_time(record))).style(
+                    Style::default()
+                        .fg(app.theme.accent)
+                        .add_modifier(Modifier::BOLD),
+                )
+            } else {
+                Cell::from(start_time(record))
+            };
+            Row::new(vec![
+                date,
+                Cell::from(record.machine.hostname.clone()),
+                Cell::from(record.settings.workload.clone()),
+                Cell::from(record.settings.threads.to_string()),
+                Cell::from(format_duration(record.elapsed_secs)),
+                Cell::from(format!(
+                    "{} ({:.2} {})",
+                    record.score, record.rate, record.rate_unit
+                )),
+                Cell::from(record.outcome.clone()).style(Style::default().fg(outcome_color)),
+            ])
+        })
+        .collect();
+    let title = format!(
+        "Saved Runs ({} of {}, sorted by {} {}){}",
+        visible.len(),
+        app.history.records.len(),
+        app.history.sort.name(),
+        if app.history.descending {
+            "desc"
+        } else {
+            "asc"
+        },
+        if app.history.errors.is_empty() {
+            String::new()
+        } else {
+            format!(", {} unreadable", app.history.errors.len())
+        }
+    );
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(19),
+            Constraint::Min(10),
+            Constraint::Length(12),
+            Constraint::Length(6),
+            Constraint::Length(9),
+            Constraint::Min(20),
+            Constraint::Length(22),
+        ],
+    )
+    .header(header)
+    .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+    .block(Block::default().title(title).borders(Borders::ALL));
+    let mut state = TableState::default().with_selected(Some(app.history.selected));
+    frame.render_stateful_widget(table, chunks[0], &mut state);
+
+    let help = if app.history_filtering {
+        Line::from(vec![
+            Span::styled("Filter: ", Style::default().fg(app.theme.accent)),
+            Span::raw(app.history.filter.as_str()),
+            Span::raw("  (Enter/Esc to finish)"),
+        ])
+    } else {
+        Line::from(format!(
+            "Up/Down: select  Enter: open  C: compare{}  S: sort by next column  R: reverse  /: filter{}  Esc: back",
+            if app.history.marked.is_some() {
+                " with *"
+            } else {
+                " (mark two runs)"
+            },
+            if app.history.filter.is_empty() {
+                String::new()
+            } else {
+                format!(" ('{}')", app.history.filter)
+            }
+        ))
+    };
+    frame.render_widget(
+        Paragraph::new(help).block(Block::default().borders(Borders::ALL)),
+        chunks[1],
+    );
+}
+
+/// Colours of the older and the newer run in Compare mode.
+const COMPARE_COLORS: [Color; 2] = [Color::Cyan, Color::LightMagenta];
+
+/// Draws two saved runs on shared axes next to a table of their differences.
+fn ui_compare_mode(frame: &mut Frame, app: &App) {
+    let Some((before, after)) = &app.comparison else {
+        return;
+    };
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Ratio(3, 5), Constraint::Ratio(2, 5)])
+        .split(frame.area());
+    let charts = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Ratio(1, 3),
+            Constraint::Ratio(1, 3),
+            Constraint::Ratio(1, 3),
+        ])
+        .split(chunks[0]);
+
+    render_compare_chart(frame, charts[0], "CPU Usage", "%", app, 100.0, |sample| {
+        Some(sample.usage)
+    });
+    let rate_unit = if before.rate_unit == after.rate_unit {
+        after.rate_unit.clone()
+    } else {
+        format!("{} / {}", before.rate_unit, after.rate_unit)
+    };
+    render_compare_chart(
+        frame,
+        charts[1],
+        "Throughput",
+        &rate_unit,
+        app,
+        0.0,
+        |sample| sample.throughput,
+    );
+    render_compare_chart(
+        frame,
+        charts[2],
+        "Hottest Sensor",
+        "°C",
+        app,
+        0.0,
+        |sample| sample.temperature.map(f64::from),
+    );
+
+    let text_row = |name: &str, value: &dyn Fn(&RunRecord) -> String| {
+        Row::new(vec![
+            Cell::from(name.to_string()),
+            Cell::from(value(before)),
+            Cell::from(value(after)),
+            Cell::from(""),
+        ])
+    };
+    let header = Row::new(vec![
+        Cell::from("Metric"),
+        Cell::from("Before").style(Style::default().fg(COMPARE_COLORS[0])),
+        Cell::from("After").style(Style::default().fg(COMPARE_COLORS[1])),
+        Cell::from("Change"),
+    ])
+    .style(Style::default().add_modifier(Modifier::BOLD));
+    let rows = vec![
+        text_row("Date", &|r| start_time(r)),
+        text_row("Machine", &|r| r.machine.hostname.clone()),
+        text_row("Workload", &|r| r.settings.workload.clone()),
+        text_row("Threads", &|r| r.settings.threads.to_string()),
+        text_row("Duration", &|r| format_duration(r.elapsed_secs)),
+        text_row("Outcome", &|r| r.outcome.clone()),
+        delta_row(
+            "Score",
+            Some(before.score as f64),
+            Some(after.score as f64),
+            |v| format!("{:.0}", v),
+            true,
+        ),
+        delta_row(
+            "Rate",
+            Some(before.rate),
+            Some(after.rate),
+            |v| format!("{:.2}", v),
+            true,
+        ),
+        delta_row(
+            "Avg MHz",
+            before.throttling.average_frequency,
+            after.throttling.average_frequency,
+            |v| format!("{:.0}", v),
+            true,
+        ),
+        delta_row(
+            "Peak °C",
+            before.peak_temperature.map(f64::from),
+            after.peak_temperature.map(f64::from),
+            |v| format!("{:.1}", v),
+            false,
+        ),
+        delta_row(
+            "Throttled s",
+            Some(before.throttling.throttled_secs),
+            Some(after.throttling.throttled_secs),
+            |v| format!("{:.0}", v),
+            false,
+        ),
+    ];
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(12),
+            Constraint::Ratio(1, 3),
+            Constraint::Ratio(1, 3),
+            Constraint::Ratio(1, 3),
+        ],
+    )
+    .header(header)
+    .block(
+        Block::default()
+            .title("Before / After (Esc: back)")
+            .borders(Borders::ALL),
+    );
+    frame.render_widget(table, chunks[1]);
+}
+
+/// Draws one value of both compared runs on shared axes. The y axis reaches at
+/// least `min_y`.
+fn render_compare_chart(
+    frame: &mut Frame,
+    area: Rect,
+    title: &str,
+    unit: &str,
+    app: &App,
+    min_y: f64,
+    value: impl Fn(&RunSample) -> Option<f64>,
+) {
+    let Some((before, after)) = &app.comparison else {
+        return;
+    };
+    let runs = [before, after];
+    let x_end = runs.iter().map(|run| run.elapsed_secs).fold(1.0, f64::max);
+    let points: Vec<Vec<(f64, f64)>> = runs
+        .iter()
+        .map(|run| {
+            run.series(&value)
+                .points(0.0, x_end, area.width as usize * 2)
+        })
+        .collect();
+    let highest = points.iter().flatten().map(|&(_, v)| v).fold(0.0, f64::max);
+    let max_y = if highest <= min_y {
+        min_y.max(1.0)
+    } else {
+        highest * 1.1
+    };
+
+    let datasets = runs
+        .iter()
+        .zip(&points)
+        .zip(COMPARE_COLORS)
+        .map(|((run, points), color)| {
+            Dataset::default()
+                .name(start_time(run))
+                .marker(symbols::Marker::Braille)
+                .style(Style::default().fg(color))
+                .graph_type(GraphType::Line)
+                .data(points)
+        })
+        .collect();
+    let title = if points.iter().all(Vec::is_empty) {
+        format!("{} (not recorded)", title)
+    } else {
+        title.to_string()
+    };
+    let chart = Chart::new(datasets)
+        .block(Block::default().title(title).borders(Borders::ALL))
+        // Keep the legend telling the runs apart, even on short charts
+        .hidden_legend_constraints((Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)))
+        .x_axis(
+            Axis::default()
+                .title("Time (s)")
+                .style(Style::default().fg(Color::Gray))
+                .bounds([0.0, x_end])
+                .labels(vec![
+                    Span::styled("0", Style::default().fg(app.theme.text)),
+                    Span::styled(
+                        format!("{:.0}", x_end / 2.0),
+                        Style::default().fg(app.theme.text),
+                    ),
+                    Span::styled(format!("{:.0}", x_end), Style::default().fg(app.theme.text)),
+                ]),
+        )
+        .y_axis(
+            Axis::default()
+                .title(unit.to_string())
+                .style(Style::default().fg(Color::Gray))
+                .bounds([0.0, max_y])
+                .labels(vec![
+                    Span::styled("0", Style::default().fg(app.theme.text)),
+                    Span::styled(
+                        format!("{:.1}", max_y / 2.0),
+                        Style::default().fg(app.theme.text),
+                    ),
+                    Span::styled(format!("{:.1}", max_y), Style::default().fg(app.theme.text)),
+                ]),
+        );
+    frame.render_widget(chart, area);
+}
+
+/// A row of the compare table with the change from `before` to `after`, green
+/// when it is an improvement.
+fn delta_row(
+    name: &str,
+    before: Option<f64>,
+    after: Option<f64>,
+    format: fn(f64) -> String,
+    higher_is_better: bool,
+) -> Row<'static> {
+    let shown = |value: Option<f64>| value.map_or("-".to_string(), format);
+    let change = match (before, after) {
+        (Some(before), Some(after)) if after != before => {
+            let difference = after - before;
+            let text = if before != 0.0 {
+                format!("{:+.1}%", difference / before.abs() * 100.0)
+            } else if difference > 0.0 {
+                format!("+{}", format(difference))
+            } else {
+                format(difference)
+            };
+            let better = (difference > 0.0) == higher_is_better;
+            Cell::from(text).style(Style::default().fg(if better {
+                Color::Green
+            } else {
+                Color::Red
+            }))
+        }
+        (Some(_), Some(_)) => Cell::from("="),
+        _ => Cell::from("-"),
+    };
+    Row::new(vec![
+        Cell::from(name.to_string()),
+        Cell::from(shown(before)),
+        Cell::from(shown(after)),
+        change,
+    ])
+}
+
+/// Describes the outcome of the last run for the finished popup.
+fn finished_summary_lines(app: &App) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    match &app.last_result {
+        Some(Ok(result)) if result.thermal_abort.is_some() => {
+            let abort = result.thermal_abort.as_ref().unwrap();
+            lines.push(Line::styled(
+                "ABORTED: thermal limit",
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            ));
+            lines.push(Line::from(format!(
+                "{} {:.1}°C at {:.0}s, {}",
+                abort.sensor,
+                abort.temperature,
+                abort.at.as_secs_f64(),
+                abort.rule
+            )));
+        }
+        Some(Ok(result)) if result.failed() => {
+            let first = &result.errors[0];
+            lines.push(Line::styled(
+                format!("FAILED: {} wrong result(s)", result.errors.len()),
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            ));
+            lines.push(Line::from(format!(
+                "First: thread {} on CPU {} at {:.1}s",
+                first.thread_index,
+                first.core.map_or("?".to_string(), |core| core.to_string()),
+                first.at.as_secs_f64()
+            )));
+        }
+        Some(Ok(result)) => {
+            lines.push(Line::styled(
+                format!("PASSED: {} score {}", result.workload, result.score),
+                Style::default()
+                    .fg(Color::Green)
+                    .add_modifier(Modifier::BOLD),
+            ));
+            lines.push(Line::from(format!(
+                "{:.2} {} over {} workers",
+                result.rate,
+                result.rate_unit,
+                result.workers.len()
+            )));
+        }
+        Some(Err(e)) => lines.push(Line::styled(e.clone(), Style::default().fg(Color::Red))),
+        None => lines.push(Line::from("Test stopped")),
+    }
+
+    if let Some(Ok(result)) = &app.last_result
+        && let Some(slowest) = result.slowest_worker()
+        && result.workers.len() > 1
+    {
+        let mean = result.mean_worker_score();
+        let below = if mean > 0.0 {
+            (1.0 - slowest.score as f64 / mean) * 100.0
+        } else {
+            0.0
+        };
+        lines.push(Line::from(format!(
+            "Slowest: thread {} on CPU {}, {:.1}% below mean",
+            slowest.thread_index,
+            slowest
+                .core
+                .map_or("?".to_string(), |core| core.to_string()),
+            below
+        )));
+    }
+
+    if let Some(Ok(result)) = &app.last_result {
+        let throttling = &result.throttling;
+        if throttling.throttled() {
+            lines.push(Line::styled(
+                format!(
+                    "Throttling: {:.0}s from {:.0}s ({} clock drops, {} rate drops)",
+                    throttling.throttled_for.as_secs_f64(),
+                    throttling.first_at.unwrap_or_default().as_secs_f64(),
+                    throttling.frequency_drops,
+                    throttling.rate_drops
+                ),
+                Style::default().fg(Color::Yellow),
+            ));
+        } else if let Some(frequency) = throttling.average_frequency {
+            lines.push(Line::from(format!(
+                "No throttling, {:.0} MHz average",
+                frequency
+            )));
+        }
+    }
+
+    match &app.saved_record {
+        Some(Ok(path)) => lines.push(Line::from(format!("Saved to {}", path.display()))),
+        Some(Err(e)) => lines.push(Line::styled(e.clone(), Style::default().fg(Color::Red))),
+        None => {}
+    }
+    lines
 }
 
 /// Draws the application UI in the "Time's Up!" popup mode.
@@ -472,8 +2319,9 @@ fn ui_finished_popup_mode(frame: &mut Frame, app: &mut App) {
     );
 
     // Calculate popup size and position (centered)
-    let popup_width = 40;
-    let popup_height = 10;
+    let summary = finished_summary_lines(app);
+    let popup_width = 56;
+    let popup_height = 9 + summary.len() as u16;
     let popup_area = Rect::new(
         (area.width.saturating_sub(popup_width)) / 2,
         (area.height.saturating_sub(popup_height)) / 2,
@@ -484,12 +2332,13 @@ fn ui_finished_popup_mode(frame: &mut Frame, app: &mut App) {
     let popup_chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length(1), // Title
-            Constraint::Length(1), // Message
-            Constraint::Length(1), // Spacer
-            Constraint::Length(1), // Run Again
-            Constraint::Length(1), // Exit
-            Constraint::Min(0),    // Spacer
+            Constraint::Length(1),                    // Title
+            Constraint::Length(1),                    // Message
+            Constraint::Length(summary.len() as u16), // Result summary
+            Constraint::Length(1),                    // Spacer
+            Constraint::Length(1),                    // Run Again
+            Constraint::Length(1),                    // Exit
+            Constraint::Min(0),                       // Spacer
         ])
         .margin(1)
         .split(popup_area);
@@ -500,7 +2349,7 @@ fn ui_finished_popup_mode(frame: &mut Frame, app: &mut App) {
             Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
         )]))
         .borders(Borders::ALL)
-        .style(Style::default().bg(Color::Black).fg(Color::White)); // Black background for the popup
+        .style(Style::default().bg(app.theme.background).fg(app.theme.text)); // Solid background for the popup
 
     frame.render_widget(popup_block, popup_area);
 
@@ -508,31 +2357,320 @@ fn ui_finished_popup_mode(frame: &mut Frame, app: &mut App) {
         Paragraph::new("Your timer has finished!").alignment(ratatui::layout::Alignment::Center);
     frame.render_widget(message, popup_chunks[1]);
 
+    frame.render_widget(
+        Paragraph::new(Text::from(summary)).alignment(ratatui::layout::Alignment::Center),
+        popup_chunks[2],
+    );
+
     let run_again_style = if matches!(app.finished_popup_selected_option, PopupOption::RunAgain) {
         Style::default()
-            .fg(Color::Green)
+            .fg(app.theme.selected)
             .add_modifier(Modifier::BOLD)
     } else {
-        Style::default().fg(Color::White)
+        Style::default().fg(app.theme.text)
     };
     let run_again_text = Paragraph::new("Run Again (Enter)")
         .style(run_again_style)
         .alignment(ratatui::layout::Alignment::Center);
-    frame.render_widget(run_again_text, popup_chunks[3]);
+    frame.render_widget(run_again_text, popup_chunks[4]);
 
     let exit_style = if matches!(app.finished_popup_selected_option, PopupOption::Exit) {
         Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
     } else {
-        Style::default().fg(Color::White)
+        Style::default().fg(app.theme.text)
     };
     let exit_text = Paragraph::new("Exit (Q/Esc)")
         .style(exit_style)
         .alignment(ratatui::layout::Alignment::Center);
-    frame.render_widget(exit_text, popup_chunks[4]);
+    frame.render_widget(exit_text, popup_chunks[5]);
+}
+
+/// Draws the combined report of a finished plan, one row per stage.
+fn ui_plan_report_mode(frame: &mut Frame, app: &App) {
+    let Some(report) = &app.plan_report else {
+        return;
+    };
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(4),
+            Constraint::Min(0),
+            Constraint::Length(3),
+        ])
+        .split(frame.area());
+
+    let outcome_color = if report.outcome == "passed" {
+        Color::Green
+    } else {
+        Color::Red
+    };
+    let mut summary = vec![Line::from(vec![
+        Span::styled(
+            report.outcome.to_uppercase(),
+            Style::default()
+                .fg(outcome_color)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(format!(
+            ": {} of {} stages passed in {} on {}",
+            report.stages.iter().filter(|stage| stage.passed()).count(),
+            report.stages.len(),
+            format_duration((report.finished_at - report.started_at).as_seconds_f64()),
+            report.machine.hostname
+        )),
+    ])];
+    summary.push(match &app.saved_plan_report {
+        Some(Ok(path)) => Line::from(format!("Saved to {}", path.display())),
+        Some(Err(e)) => Line::styled(e.clone(), Style::default().fg(Color::Red)),
+        None => Line::from(""),
+    });
+    frame.render_widget(
+        Paragraph::new(summary).block(
+            Block::default()
+                .title(Span::styled(
+                    format!("Plan {}", report.name),
+                    Style::default()
+                        .fg(app.theme.accent)
+                        .add_modifier(Modifier::BOLD),
+                ))
+                .borders(Borders::ALL),
+        ),
+        chunks[0],
+    );
+
+    // Sweeps show their scaling above the stages
+    let stages_area = match &report.scaling {
+        Some(scaling) => {
+            let parts = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Ratio(3, 5), Constraint::Ratio(2, 5)])
+                .split(chunks[1]);
+            render_scaling(frame, scaling, app.theme, parts[0]);
+            parts[1]
+        }
+        None => chunks[1],
+    };
+
+    let header = Row::new([
+        "Stage", "Time", "Outcome", "Score", "Usage", "Avg °C", "Peak °C", "MHz", "Details",
+    ])
+    .style(
+        Style::default()
+            .fg(Color::Yellow)
+            .add_modifier(Modifier::BOLD),
+    );
+    let optional = |value: Option<String>| value.unwrap_or("-".to_string());
+    let rows: Vec<Row> = report
+        .stages
+        .iter()
+        .map(|stage| {
+            let outcome_color = if stage.passed() {
+                Color::Green
+            } else {
+                Color::Red
+            };
+            Row::new(vec![
+                Cell::from(stage.name.clone()),
+                Cell::from(format_duration(stage.elapsed_secs)),
+                Cell::from(stage.outcome.clone()).style(Style::default().fg(outcome_color)),
+                Cell::from(match (stage.score, stage.rate) {
+                    (Some(score), rate) => format!(
+                        "{} ({:.2} {})",
+                        score,
+                        rate.unwrap_or(0.0),
+                        stage.rate_unit.as_deref().unwrap_or("")
+                    ),
+                    // Bandwidth stages measure a rate without a score
+                    (None, Some(rate)) => {
+                        format!("{:.2} {}", rate, stage.rate_unit.as_deref().unwrap_or(""))
+                    }
+                    (None, None) => "-".to_string(),
+                }),
+                Cell::from(optional(stage.average_usage.map(|u| format!("{:.0}%", u)))),
+                Cell::from(optional(
+                    stage.average_temperature.map(|t| format!("{:.1}", t)),
+                )),
+                Cell::from(optional(
+                    stage.peak_temperature.map(|t| format!("{:.1}", t)),
+                )),
+                Cell::from(optional(
+                    stage.average_frequency.map(|f| format!("{:.0}", f)),
+                )),
+                Cell::from(stage.detail.clone().unwrap_or_default()),
+            ])
+        })
+        .collect();
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Min(20),
+            Constraint::Length(7),
+            Constraint::Length(22),
+            Constraint::Min(20),
+            Constraint::Length(6),
+            Constraint::Length(7),
+            Constraint::Length(8),
+            Constraint::Length(5),
+            Constraint::Min(20),
+        ],
+    )
+    .header(header)
+    .block(Block::default().title("Stages").borders(Borders::ALL));
+    frame.render_widget(table, stages_area);
+
+    frame.render_widget(
+        Paragraph::new("Enter: back to the input form  Q/Esc: exit")
+            .block(Block::default().borders(Borders::ALL)),
+        chunks[2],
+    );
+}
+
+/// Draws the scaling table of a sweep next to a chart of throughput against the
+/// thread count, with perfect linear scaling for reference.
+fn render_scaling(frame: &mut Frame, scaling: &ScalingReport, theme: Theme, area: Rect) {
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)])
+        .split(area);
+
+    let header = Row::new(["Threads", "Rate", "Speedup", "Efficiency", "Added/thread"]).style(
+        Style::default()
+            .fg(Color::Yellow)
+            .add_modifier(Modifier::BOLD),
+    );
+    let rows: Vec<Row> = scaling
+        .steps
+        .iter()
+        .map(|step| {
+            let row = Row::new(vec![
+                Cell::from(step.threads.to_string()),
+                Cell::from(format!("{:.2} {}", step.rate, scaling.rate_unit)),
+                Cell::from(format!("{:.2}x", step.speedup)),
+                Cell::from(format!("{:.0}%", step.efficiency * 100.0)),
+                Cell::from(format!("{:.0}%", step.added_per_thread * 100.0)),
+            ]);
+            if scaling.knee == Some(step.threads) {
+                row.style(
+                    Style::default()
+                        .fg(theme.accent)
+                        .add_modifier(Modifier::BOLD),
+                )
+            } else {
+                row
+            }
+        })
+        .collect();
+    let count = |n: usize, noun: &str| format!("{} {}{}", n, noun, if n == 1 { "" } else { "s" });
+    let physical = scaling.physical_cores.map_or(String::new(), |cores| {
+        format!(", {}", count(cores, "physical core"))
+    });
+    let title = match scaling.knee {
+        Some(threads) => format!(
+            "Scaling (gains drop after {}{})",
+            count(threads, "thread"),
+            physical
+        ),
+        None => format!("Scaling (no drop in gains{})", physical),
+    };
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(8),
+            Constraint::Min(16),
+            Constraint::Length(8),
+            Constraint::Length(11),
+            Constraint::Length(13),
+        ],
+    )
+    .header(header)
+    .block(Block::default().title(title).borders(Borders::ALL));
+    frame.render_widget(table, chunks[0]);
+
+    let measured: Vec<(f64, f64)> = scaling
+        .steps
+        .iter()
+        .map(|step| (step.threads as f64, step.rate))
+        .collect();
+    let single = scaling.steps.first().map_or(0.0, |step| step.rate);
+    let linear: Vec<(f64, f64)> = measured
+        .iter()
+        .map(|&(threads, _)| (threads, single * threads))
+        .collect();
+    let max_x = measured
+        .last()
+        .map_or(1.0, |&(threads, _)| threads)
+        .max(2.0);
+    let max_y = linear
+        .iter()
+        .chain(&measured)
+        .map(|&(_, rate)| rate)
+        .fold(1.0, f64::max)
+        * 1.1;
+    let datasets = vec![
+        Dataset::default()
+            .name("Linear")
+            .marker(symbols::Marker::Braille)
+            .style(Style::default().fg(Color::DarkGray))
+            .graph_type(GraphType::Line)
+            .data(&linear),
+        Dataset::default()
+            .name("Measured")
+            .marker(symbols::Marker::Braille)
+            .style(Style::default().fg(Color::Cyan))
+            .graph_type(GraphType::Line)
+            .data(&measured),
+    ];
+    let chart = Chart::new(datasets)
+        .block(
+            Block::default()
+                .title("Throughput vs Threads")
+                .borders(Borders::ALL),
+        )
+        .hidden_legend_constraints((Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)))
+        .x_axis(
+            Axis::default()
+                .title("Threads")
+                .style(Style::default().fg(Color::Gray))
+                .bounds([1.0, max_x])
+                .labels(vec![
+                    Span::styled("1", Style::default().fg(theme.text)),
+                    Span::styled(
+                        format!("{:.0}", (1.0 + max_x) / 2.0),
+                        Style::default().fg(theme.text),
+                    ),
+                    Span::styled(format!("{:.0}", max_x), Style::default().fg(theme.text)),
+                ]),
+        )
+        .y_axis(
+            Axis::default()
+                .title(scaling.rate_unit.clone())
+                .style(Style::default().fg(Color::Gray))
+                .bounds([0.0, max_y])
+                .labels(vec![
+                    Span::styled("0", Style::default().fg(theme.text)),
+                    Span::styled(
+                        format!("{:.1}", max_y / 2.0),
+                        Style::default().fg(theme.text),
+                    ),
+                    Span::styled(format!("{:.1}", max_y), Style::default().fg(theme.text)),
+                ]),
+        );
+    frame.render_widget(chart, chunks[1]);
 }
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+async fn main() -> Result<ExitCode, Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+    if let Some(Command::Run(args)) = cli.command {
+        return Ok(headless::run(args).await);
+    }
+
+    let mut app = App::new();
+    if let Err(e) = app.apply_form_args(&cli.form) {
+        eprintln!("{}", e);
+        return Ok(ExitCode::from(2));
+    }
+
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -540,7 +2678,6 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let mut app = App::new();
     let mut running = true;
 
     while running {
@@ -549,144 +2686,302 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 Mode::Input => ui_input_mode(frame, &mut app),
                 Mode::Chart => ui_chart_mode(frame, &mut app),
                 Mode::Finished => ui_finished_popup_mode(frame, &mut app), // Draw popup
+                Mode::History => ui_history_mode(frame, &mut app),
+                Mode::Compare => ui_compare_mode(frame, &app),
+                Mode::PlanReport => ui_plan_report_mode(frame, &app),
             }
         })?;
 
         // Event handling
-        if event::poll(Duration::from_millis(50))? {
-            if let Event::Key(key) = event::read()? {
-                if key.kind == KeyEventKind::Press {
-                    if key.code == KeyCode::Char('q')
-                        || key.code == KeyCode::Char('Q')
-                        || (key.code == KeyCode::Char('c')
-                            && key.modifiers.contains(KeyModifiers::CONTROL))
-                    {
-                        running = false;
-                        if let Some(val) = &app.stress_test_handle {
-                            val.abort();
-                        }
-                    } else {
-                        match app.mode {
-                            Mode::Input => match key.code {
-                                KeyCode::Char(c) => match app.current_input_focus {
-                                    InputFocusElement::ValueInput => {
-                                        if c.is_numeric() {
-                                            app.input_text.push(c);
-                                        }
-                                    }
-                                    InputFocusElement::CpuCountSelection => {
-                                        if c.is_numeric() && app.selected_cpu_count.len() <= 2 {
-                                            app.selected_cpu_count.push(c);
-                                        }
-                                    }
-                                    InputFocusElement::UnitSelection => {}
-                                    InputFocusElement::OkButton => {}
-                                },
-                                KeyCode::Backspace => match app.current_input_focus {
-                                    InputFocusElement::ValueInput => {
-                                        app.input_text.pop();
-                                    }
-                                    InputFocusElement::CpuCountSelection => {
-                                        app.selected_cpu_count.pop();
-                                    }
-                                    InputFocusElement::UnitSelection => {}
-                                    InputFocusElement::OkButton => {}
-                                },
-                                KeyCode::Tab => {
-                                    app.current_input_focus = match app.current_input_focus {
-                                        InputFocusElement::ValueInput => {
-                                            InputFocusElement::CpuCountSelection
-                                        }
-                                        InputFocusElement::UnitSelection => {
-                                            InputFocusElement::ValueInput
-                                        }
-                                        InputFocusElement::CpuCountSelection => {
-                                            InputFocusElement::OkButton
-                                        } // Cycle to OK button
-                                        InputFocusElement::OkButton => {
-                                            InputFocusElement::UnitSelection
-                                        } // Cycle back to ValueInput
-                                    };
+        if event::poll(Duration::from_millis(50))?
+            && let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+        {
+            // Profiles, limits and filters are free text, so 'q' is typed there rather than quitting
+            let typing_profile = (matches!(app.mode, Mode::Input)
+                && matches!(
+                    app.current_input_focus,
+                    InputFocusElement::ProfileInput | InputFocusElement::ThermalLimitInput
+                ))
+                || (matches!(app.mode, Mode::History) && app.history_filtering);
+            if (!typing_profile
+                && (key.code == KeyCode::Char('q') || key.code == KeyCode::Char('Q')))
+                || (key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL))
+            {
+                running = false;
+                app.stress_test.stop_signal.store(true, Ordering::Relaxed);
+                app.memtest.stop_signal.store(true, Ordering::Relaxed);
+                if let Some(val) = &app.stress_test_handle {
+                    val.abort();
+                }
+            } else {
+                match app.mode {
+                    Mode::Input => match key.code {
+                        KeyCode::Char(c) => match app.current_input_focus {
+                            InputFocusElement::ValueInput => {
+                                if c.is_numeric() {
+                                    app.input_text.push(c);
                                 }
-                                KeyCode::Down | KeyCode::Left => {
-                                    // Navigate units only if focus is on unit selection
-                                    if matches!(
-                                        app.current_input_focus,
-                                        InputFocusElement::UnitSelection
-                                    ) {
-                                        app.selected_unit = match app.selected_unit {
-                                            TimeUnit::Seconds => TimeUnit::Minutes,
-                                            TimeUnit::Minutes => TimeUnit::Seconds,
-                                        };
-                                    }
+                            }
+                            InputFocusElement::CpuCountSelection => {
+                                if c.is_numeric() && app.selected_cpu_count.len() <= 2 {
+                                    app.selected_cpu_count.push(c);
                                 }
-                                KeyCode::Up | KeyCode::Right => {
-                                    if matches!(
-                                        app.current_input_focus,
-                                        InputFocusElement::UnitSelection
-                                    ) {
-                                        app.selected_unit = match app.selected_unit {
-                                            TimeUnit::Seconds => TimeUnit::Minutes,
-                                            TimeUnit::Minutes => TimeUnit::Seconds,
-                                        };
-                                    }
+                            }
+                            InputFocusElement::AffinityInput => {
+                                if c.is_ascii_alphanumeric() || c == ',' || c == '-' {
+                                    app.selected_affinity.push(c);
                                 }
-                                KeyCode::Enter => match app.current_input_focus {
-                                    InputFocusElement::ValueInput => {
-                                        app.current_input_focus = InputFocusElement::UnitSelection;
-                                    }
-                                    InputFocusElement::UnitSelection => {
-                                        app.current_input_focus =
-                                            InputFocusElement::CpuCountSelection;
-                                    }
-                                    InputFocusElement::CpuCountSelection => {
-                                        app.current_input_focus = InputFocusElement::OkButton;
-                                    }
-                                    InputFocusElement::OkButton => {
-                                        app.set_total_duration();
-                                    }
-                                },
-                                _ => {}
-                            },
-                            Mode::Chart => {
-                                match key.code {
-                                    KeyCode::Esc => {
-                                        app.reset_for_input(); // Escape key to go back to input mode
-                                    }
-                                    _ => {}
+                            }
+                            InputFocusElement::TargetLoadInput => {
+                                if (c.is_ascii_digit() || c == '.')
+                                    && app.selected_target_load.len() < 5
+                                {
+                                    app.selected_target_load.push(c);
                                 }
                             }
-                            Mode::Finished => match key.code {
-                                KeyCode::Enter => match app.finished_popup_selected_option {
-                                    PopupOption::RunAgain => app.reset_for_input(),
-                                    PopupOption::Exit => running = false,
-                                },
-                                KeyCode::Up | KeyCode::Down | KeyCode::Tab => {
-                                    app.finished_popup_selected_option =
-                                        match app.finished_popup_selected_option {
-                                            PopupOption::RunAgain => PopupOption::Exit,
-                                            PopupOption::Exit => PopupOption::RunAgain,
-                                        };
+                            InputFocusElement::ProfileInput => {
+                                if c.is_ascii_alphanumeric() || " ;.@%".contains(c) {
+                                    app.selected_profile.push(c);
                                 }
-                                KeyCode::Esc => {
-                                    running = false;
-                                    if let Some(val) = &app.stress_test_handle {
-                                        val.abort();
-                                    }
+                            }
+                            InputFocusElement::ThermalLimitInput => {
+                                if c.is_ascii_alphanumeric() || " ,.".contains(c) {
+                                    app.selected_thermal_limit.push(c);
                                 }
+                            }
+                            InputFocusElement::UnitSelection => {}
+                            InputFocusElement::WorkloadSelection => {}
+                            InputFocusElement::OkButton => {}
+                        },
+                        KeyCode::Backspace => match app.current_input_focus {
+                            InputFocusElement::ValueInput => {
+                                app.input_text.pop();
+                            }
+                            InputFocusElement::CpuCountSelection => {
+                                app.selected_cpu_count.pop();
+                            }
+                            InputFocusElement::AffinityInput => {
+                                app.selected_affinity.pop();
+                            }
+                            InputFocusElement::TargetLoadInput => {
+                                app.selected_target_load.pop();
+                            }
+                            InputFocusElement::ProfileInput => {
+                                app.selected_profile.pop();
+                            }
+                            InputFocusElement::ThermalLimitInput => {
+                                app.selected_thermal_limit.pop();
+                            }
+                            InputFocusElement::UnitSelection => {}
+                            InputFocusElement::WorkloadSelection => {}
+                            InputFocusElement::OkButton => {}
+                        },
+                        KeyCode::Tab => {
+                            app.current_input_focus = match app.current_input_focus {
+                                InputFocusElement::ValueInput => {
+                                    InputFocusElement::CpuCountSelection
+                                }
+                                InputFocusElement::UnitSelection => InputFocusElement::ValueInput,
+                                InputFocusElement::CpuCountSelection => {
+                                    InputFocusElement::AffinityInput
+                                }
+                                InputFocusElement::AffinityInput => {
+                                    InputFocusElement::TargetLoadInput
+                                }
+                                InputFocusElement::TargetLoadInput => {
+                                    InputFocusElement::ProfileInput
+                                }
+                                InputFocusElement::ProfileInput => {
+                                    InputFocusElement::ThermalLimitInput
+                                }
+                                InputFocusElement::ThermalLimitInput => {
+                                    InputFocusElement::WorkloadSelection
+                                }
+                                InputFocusElement::WorkloadSelection => InputFocusElement::OkButton, // Cycle to OK button
+                                InputFocusElement::OkButton => InputFocusElement::UnitSelection, // Cycle back to ValueInput
+                            };
+                        }
+                        KeyCode::Down | KeyCode::Left => {
+                            // Navigate units only if focus is on unit selection
+                            match app.current_input_focus {
+                                InputFocusElement::UnitSelection => {
+                                    app.selected_unit = match app.selected_unit {
+                                        TimeUnit::Seconds => TimeUnit::Minutes,
+                                        TimeUnit::Minutes => TimeUnit::Seconds,
+                                    };
+                                }
+                                InputFocusElement::WorkloadSelection => app.cycle_workload(false),
                                 _ => {}
-                            },
+                            }
+                        }
+                        KeyCode::Up | KeyCode::Right => match app.current_input_focus {
+                            InputFocusElement::UnitSelection => {
+                                app.selected_unit = match app.selected_unit {
+                                    TimeUnit::Seconds => TimeUnit::Minutes,
+                                    TimeUnit::Minutes => TimeUnit::Seconds,
+                                };
+                            }
+                            InputFocusElement::WorkloadSelection => app.cycle_workload(true),
+                            _ => {}
+                        },
+                        KeyCode::Enter => match app.current_input_focus {
+                            InputFocusElement::ValueInput => {
+                                app.current_input_focus = InputFocusElement::UnitSelection;
+                            }
+                            InputFocusElement::UnitSelection => {
+                                app.current_input_focus = InputFocusElement::CpuCountSelection;
+                            }
+                            InputFocusElement::CpuCountSelection => {
+                                app.current_input_focus = InputFocusElement::AffinityInput;
+                            }
+                            InputFocusElement::AffinityInput => {
+                                app.current_input_focus = InputFocusElement::TargetLoadInput;
+                            }
+                            InputFocusElement::TargetLoadInput => {
+                                app.current_input_focus = InputFocusElement::ProfileInput;
+                            }
+                            InputFocusElement::ProfileInput => {
+                                app.current_input_focus = InputFocusElement::ThermalLimitInput;
+                            }
+                            InputFocusElement::ThermalLimitInput => {
+                                app.current_input_focus = InputFocusElement::WorkloadSelection;
+                            }
+                            InputFocusElement::WorkloadSelection => {
+                                app.current_input_focus = InputFocusElement::OkButton;
+                            }
+                            InputFocusElement::OkButton => {
+                                app.set_total_duration();
+                            }
+                        },
+                        KeyCode::F(2) => app.open_history(),
+                        KeyCode::F(3) => app.start_sweep(),
+                        _ => {}
+                    },
+                    Mode::Chart => match key.code {
+                        KeyCode::Esc if app.reviewing.is_some() => app.close_record(),
+                        KeyCode::Esc if app.plan.is_some() => app.stop_plan(),
+                        KeyCode::Esc => app.reset_for_input(), // Escape key to go back to input mode
+                        KeyCode::Char('v') | KeyCode::Char('V') => {
+                            app.usage_view = app.usage_view.next();
+                        }
+                        KeyCode::Char('+') | KeyCode::Char('=') => {
+                            let (total, now) =
+                                (app.total_duration_secs as f64, app.elapsed_secs as f64);
+                            app.viewport.zoom_in(total, now);
+                        }
+                        KeyCode::Char('-') => {
+                            let (total, now) =
+                                (app.total_duration_secs as f64, app.elapsed_secs as f64);
+                            app.viewport.zoom_out(total, now);
+                        }
+                        KeyCode::Left | KeyCode::Right => {
+                            let (total, now) =
+                                (app.total_duration_secs as f64, app.elapsed_secs as f64);
+                            let fraction = if key.code == KeyCode::Left {
+                                -0.25
+                            } else {
+                                0.25
+                            };
+                            app.viewport.pan(fraction, total, now);
+                        }
+                        KeyCode::Home => app.viewport.reset(),
+                        KeyCode::Char('p') | KeyCode::Char('P') | KeyCode::Char(' ')
+                            if app.reviewing.is_none() && app.plan.is_none() =>
+                        {
+                            app.stress_test.pause.toggle();
+                        }
+                        _ => {}
+                    },
+                    Mode::History if app.history_filtering => match key.code {
+                        KeyCode::Char(c) => {
+                            app.history.filter.push(c);
+                            app.history.selected = 0;
+                        }
+                        KeyCode::Backspace => {
+                            app.history.filter.pop();
+                            app.history.selected = 0;
+                        }
+                        KeyCode::Enter | KeyCode::Esc => app.history_filtering = false,
+                        _ => {}
+                    },
+                    Mode::History => match key.code {
+                        KeyCode::Up => app.history.move_selection(-1),
+                        KeyCode::Down => app.history.move_selection(1),
+                        KeyCode::PageUp => app.history.move_selection(-10),
+                        KeyCode::PageDown => app.history.move_selection(10),
+                        KeyCode::Enter => {
+                            if let Some(record) = app.history.selected_record().cloned() {
+                                app.open_record(record);
+                            }
+                        }
+                        KeyCode::Char('s') | KeyCode::Char('S') => {
+                            app.history.set_sort(app.history.sort.next());
+                        }
+                        KeyCode::Char('r') | KeyCode::Char('R') => app.history.reverse(),
+                        KeyCode::Char('c') | KeyCode::Char('C') => {
+                            if let Some(pair) = app.history.mark_for_comparison() {
+                                app.comparison = Some(pair);
+                                app.mode = Mode::Compare;
+                            }
+                        }
+                        KeyCode::Char('/') => app.history_filtering = true,
+                        KeyCode::Esc => app.mode = Mode::Input,
+                        _ => {}
+                    },
+                    Mode::Compare => {
+                        if key.code == KeyCode::Esc {
+                            app.comparison = None;
+                            app.mode = Mode::History;
                         }
                     }
+                    Mode::PlanReport => match key.code {
+                        KeyCode::Enter => app.reset_for_input(),
+                        KeyCode::Esc => running = false,
+                        _ => {}
+                    },
+                    Mode::Finished => match key.code {
+                        KeyCode::Enter => match app.finished_popup_selected_option {
+                            PopupOption::RunAgain => app.reset_for_input(),
+                            PopupOption::Exit => running = false,
+                        },
+                        KeyCode::Up | KeyCode::Down | KeyCode::Tab => {
+                            app.finished_popup_selected_option =
+                                match app.finished_popup_selected_option {
+                                    PopupOption::RunAgain => PopupOption::Exit,
+                                    PopupOption::Exit => PopupOption::RunAgain,
+                                };
+                        }
+                        KeyCode::Esc => {
+                            running = false;
+                            app.stress_test.stop_signal.store(true, Ordering::Relaxed);
+                            if let Some(val) = &app.stress_test_handle {
+                                val.abort();
+                            }
+                        }
+                        _ => {}
+                    },
                 }
             }
         }
 
-        if let Some(handle) = &app.stress_test_handle {
-            if handle.is_finished() && running {
-                app.stress_test_handle = None;
-                app.mode = Mode::Finished;
+        if let Some(handle) = &app.stress_test_handle
+            && handle.is_finished()
+            && running
+        {
+            if let Some(handle) = app.stress_test_handle.take() {
+                app.last_result = handle.await.ok();
             }
+            app.save_result();
+            app.mode = Mode::Finished;
+        }
+        if let Some(handle) = &app.stage_handle
+            && handle.is_finished()
+            && running
+            && let Some(handle) = app.stage_handle.take()
+        {
+            let outcome = handle.await.unwrap_or_else(|e| Err(e.to_string()));
+            app.finish_stage(outcome);
         }
         if matches!(app.mode, Mode::Chart) && running {
             app.update_data();
@@ -697,5 +2992,5 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     disable_raw_mode()?;
     execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
     terminal.show_cursor()?;
-    Ok(())
+    Ok(ExitCode::SUCCESS)
 }
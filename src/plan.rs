@@ -0,0 +1,796 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, atomic::Ordering},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use md_hardware::{
+    BandwidthReport, CpuAffinity, CpuExplosion, HardwareInfo, MemTest, MemTestReport, StressConfig,
+    StressResult, ThermalLimit, WorkloadRegistry, parse_time,
+};
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+
+use crate::{
+    results::{Machine, RunSample, save_with_samples},
+    scaling::ScalingReport,
+};
+
+/// Where combined plan reports are saved, relative to the working directory.
+pub const PLANS_DIR: &str = "results/plans";
+
+/// Share of free memory a memory stage tests when the plan does not say.
+const DEFAULT_MEMORY_SHARE: f64 = 0.5;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PlanFile {
+    name: Option<String>,
+    thermal_limit: Option<String>,
+    #[serde(rename = "stage", default)]
+    stages: Vec<StageFile>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StageFile {
+    kind: StageKind,
+    name: Option<String>,
+    duration: String,
+    workload: Option<String>,
+    threads: Option<usize>,
+    affinity: Option<String>,
+    target_load: Option<f32>,
+    share: Option<f64>,
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum StageKind {
+    #[serde(alias = "cooldown")]
+    Idle,
+    Cpu,
+    SingleCore,
+    Memory,
+    Bandwidth,
+}
+
+/// A qualification procedure read from a TOML file, see `plans/qualification.toml`
+/// for the format. Single-core stages are expanded to one stage per CPU.
+#[derive(Clone, Debug)]
+pub struct TestPlan {
+    pub name: String,
+    pub path: PathBuf,
+    pub stages: Vec<Stage>,
+    /// A core-scaling sweep: the same workload on more threads at every stage.
+    pub sweep: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct Stage {
+    pub name: String,
+    pub duration_secs: u64,
+    pub task: StageTask,
+}
+
+#[derive(Clone, Debug)]
+pub enum StageTask {
+    /// No load, the machine is only sampled.
+    Idle,
+    Cpu(StressConfig),
+    /// Not watched by the plan's thermal limit, which only CPU stages check.
+    Memory {
+        share: f64,
+        threads: usize,
+    },
+    /// STREAM memory bandwidth, not watched by the thermal limit either.
+    Bandwidth {
+        threads: usize,
+    },
+}
+
+/// What a stage produced when it ran.
+pub enum StageOutcome {
+    Idle,
+    Cpu(Box<StressResult>),
+    Memory(MemTestReport),
+    Bandwidth(BandwidthReport),
+}
+
+impl TestPlan {
+    /// Reads and checks a plan file. Workload names may be abbreviated as in the
+    /// input form.
+    pub fn load(
+        path: &Path,
+        workloads: &WorkloadRegistry,
+        logical_cores: usize,
+    ) -> Result<TestPlan, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+        let file: PlanFile =
+            toml::from_str(&text).map_err(|e| format!("Invalid plan {}: {}", path.display(), e))?;
+        let thermal_limit = file
+            .thermal_limit
+            .as_deref()
+            .map(ThermalLimit::parse)
+            .transpose()?;
+
+        let mut stages = Vec::new();
+        for (i, stage) in file.stages.iter().enumerate() {
+            let expanded = expand_stage(stage, workloads, logical_cores, thermal_limit.as_ref())
+                .map_err(|e| format!("Stage {}: {}", i + 1, e))?;
+            stages.extend(expanded);
+        }
+        if stages.is_empty() {
+            return Err(format!("Plan {} has no stages", path.display()));
+        }
+        Ok(TestPlan {
+            name: file.name.unwrap_or_else(|| {
+                path.file_stem().map_or("plan".to_string(), |stem| {
+                    stem.to_string_lossy().into_owned()
+                })
+            }),
+            path: path.to_path_buf(),
+            stages,
+            sweep: false,
+        })
+    }
+
+    pub fn duration_secs(&self) -> u64 {
+        self.stages.iter().map(|stage| stage.duration_secs).sum()
+    }
+}
+
+fn expand_stage(
+    stage: &StageFile,
+    workloads: &WorkloadRegistry,
+    logical_cores: usize,
+    thermal_limit: Option<&ThermalLimit>,
+) -> Result<Vec<Stage>, String> {
+    let duration_secs = parse_time(&stage.duration)
+        .filter(|duration| !duration.is_zero())
+        .map(|duration| duration.as_secs_f64().ceil() as u64)
+        .ok_or(format!(
+            "Invalid duration '{}', expected e.g. 90s, 5m or 1h",
+            stage.duration
+        ))?;
+    let affinity = CpuAffinity::parse(stage.affinity.as_deref().unwrap_or(""))?;
+    let pinned_cpus = affinity.resolve(logical_cores)?;
+    let threads = stage
+        .threads
+        .unwrap_or(pinned_cpus.as_ref().map_or(logical_cores, Vec::len));
+    if threads == 0 {
+        return Err("Threads must be at least 1".to_string());
+    }
+    if let Some(percent) = stage.target_load
+        && !(percent > 0.0 && percent <= 100.0)
+    {
+        return Err(format!(
+            "Target load must be between 0 and 100%, got {}",
+            percent
+        ));
+    }
+    let workload = workloads.resolve(stage.workload.as_deref().unwrap_or("fibonacci"))?;
+    let cpu_config = |threads: usize, affinity: CpuAffinity| {
+        let mut config =
+            StressConfig::new(duration_secs, threads, workload).with_affinity(affinity);
+        if let Some(limit) = thermal_limit {
+            config = config.with_thermal_limit(limit.clone());
+        }
+        if let Some(percent) = stage.target_load {
+            config = config.with_target_load(percent);
+        }
+        config
+    };
+
+    let stages = match stage.kind {
+        StageKind::Idle => vec![Stage {
+            name: stage.name.clone().unwrap_or("Idle".to_string()),
+            duration_secs,
+            task: StageTask::Idle,
+        }],
+        StageKind::Cpu => vec![Stage {
+            name: stage
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("{} on {} threads", workload, threads)),
+            duration_secs,
+            task: StageTask::Cpu(cpu_config(threads, affinity)),
+        }],
+        StageKind::SingleCore => {
+            let name = stage
+                .name
+                .clone()
+                .unwrap_or(format!("{} single core", workload));
+            pinned_cpus
+                .unwrap_or_else(|| (0..logical_cores).collect())
+                .into_iter()
+                .map(|cpu| Stage {
+                    name: format!("{}, CPU {}", name, cpu),
+                    duration_secs,
+                    task: StageTask::Cpu(cpu_config(1, CpuAffinity::Cpus(vec![cpu]))),
+                })
+                .collect()
+        }
+        StageKind::Memory => {
+            let share = stage.share.unwrap_or(DEFAULT_MEMORY_SHARE);
+            if !(share > 0.0 && share <= 1.0) {
+                return Err(format!(
+                    "Memory share must be between 0 and 1, got {}",
+                    share
+                ));
+            }
+            vec![Stage {
+                name: stage.name.clone().unwrap_or("Memory test".to_string()),
+                duration_secs,
+                task: StageTask::Memory { share, threads },
+            }]
+        }
+        StageKind::Bandwidth => vec![Stage {
+            name: stage
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("Memory bandwidth on {} threads", threads)),
+            duration_secs,
+            task: StageTask::Bandwidth { threads },
+        }],
+    };
+    Ok(stages)
+}
+
+impl Stage {
+    pub fn kind(&self) -> &'static str {
+        match self.task {
+            StageTask::Idle => "idle",
+            StageTask::Cpu(_) => "cpu",
+            StageTask::Memory { .. } => "memory",
+            StageTask::Bandwidth { .. } => "bandwidth",
+        }
+    }
+
+    /// Starts the stage in the background. Idle, CPU and bandwidth stages end early on
+    /// the stress tester's stop signal, memory stages on the memory test's. CPU and
+    /// bandwidth stages run on the stress tester's worker threads.
+    pub fn start(
+        &self,
+        stress_test: &CpuExplosion,
+        memtest: &MemTest,
+    ) -> JoinHandle<Result<StageOutcome, String>> {
+        let duration_secs = self.duration_secs;
+        match &self.task {
+            StageTask::Idle => {
+                let stop_signal = Arc::clone(&stress_test.stop_signal);
+                tokio::spawn(async move {
+                    let end = tokio::time::Instant::now() + Duration::from_secs(duration_secs);
+                    while tokio::time::Instant::now() < end && !stop_signal.load(Ordering::Relaxed)
+                    {
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                    }
+                    Ok(StageOutcome::Idle)
+                })
+            }
+            StageTask::Cpu(config) => {
+                let tester = stress_test.clone();
+                let config = config.clone();
+                tokio::spawn(async move {
+                    tester
+                        .stress_test_cpu(&config)
+                        .await
+                        .map(|result| StageOutcome::Cpu(Box::new(result)))
+                })
+            }
+            StageTask::Memory { share, threads } => {
+                let memtest = memtest.clone();
+                let (share, threads) = (*share, *threads);
+                tokio::spawn(async move {
+                    memtest
+                        .test_memory(share, duration_secs, threads)
+                        .await
+                        .map(StageOutcome::Memory)
+                })
+            }
+            StageTask::Bandwidth { threads } => {
+                let bandwidth = stress_test.memory_bandwidth();
+                let threads = *threads;
+                tokio::spawn(async move {
+                    let report = bandwidth.stress_test_memory(duration_secs, threads).await;
+                    Ok(StageOutcome::Bandwidth(report))
+                })
+            }
+        }
+    }
+}
+
+/// Summary of one stage in the combined report.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StageReport {
+    pub name: String,
+    /// "idle", "cpu", "memory" or "bandwidth".
+    pub kind: String,
+    /// Seconds into the plan at which the stage started.
+    pub start_secs: f64,
+    pub elapsed_secs: f64,
+    /// "passed", "failed", "aborted: thermal limit", "stopped", "skipped",
+    /// "inconclusive" for a memory test without a complete pass, or why it could not
+    /// run.
+    pub outcome: String,
+    pub score: Option<u64>,
+    pub rate: Option<f64>,
+    pub rate_unit: Option<String>,
+    /// Rate of every worker thread, for CPU and bandwidth stages.
+    #[serde(default)]
+    pub thread_rates: Vec<f64>,
+    /// First error, memory tested and similar.
+    pub detail: Option<String>,
+    pub average_usage: Option<f64>,
+    pub average_temperature: Option<f32>,
+    pub peak_temperature: Option<f32>,
+    pub average_frequency: Option<f64>,
+}
+
+impl StageReport {
+    /// Sums up a finished stage from its outcome and the samples taken while it ran.
+    pub fn new(
+        stage: &Stage,
+        start_secs: f64,
+        end_secs: f64,
+        outcome: Result<StageOutcome, String>,
+        samples: &[RunSample],
+    ) -> Self {
+        let during: Vec<&RunSample> = samples
+            .iter()
+            .filter(|sample| sample.elapsed > start_secs && sample.elapsed <= end_secs)
+            .collect();
+        let average = |values: Vec<f64>| {
+            (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+        };
+        let temperatures: Vec<f32> = during.iter().filter_map(|s| s.temperature).collect();
+        let mut report = StageReport {
+            name: stage.name.clone(),
+            kind: stage.kind().to_string(),
+            start_secs,
+            elapsed_secs: end_secs - start_secs,
+            outcome: "passed".to_string(),
+            score: None,
+            rate: None,
+            rate_unit: None,
+            thread_rates: Vec::new(),
+            detail: None,
+            average_usage: average(during.iter().map(|s| s.usage).collect()),
+            average_temperature: average(temperatures.iter().map(|&t| t as f64).collect())
+                .map(|t| t as f32),
+            peak_temperature: temperatures.iter().copied().reduce(f32::max),
+            average_frequency: average(during.iter().filter_map(|s| s.frequency).collect()),
+        };
+        match outcome {
+            Ok(StageOutcome::Idle) => report.outcome = "done".to_string(),
+            Ok(StageOutcome::Cpu(result)) => {
+                report.outcome = result.outcome().to_string();
+                report.score = Some(result.score);
+                report.rate = Some(result.rate);
+                report.rate_unit = Some(result.rate_unit.to_string());
+                report.thread_rates = result.workers.iter().map(|w| w.rate).collect();
+                report.detail = match (&result.thermal_abort, result.errors.first()) {
+                    (Some(abort), _) => Some(format!(
+                        "{} {:.1}°C at {:.0}s",
+                        abort.sensor,
+                        abort.temperature,
+                        abort.at.as_secs_f64()
+                    )),
+                    (None, Some(error)) => Some(format!(
+                        "{} wrong result(s), first on thread {}: {}",
+                        result.errors.len(),
+                        error.thread_index,
+                        error.message
+                    )),
+                    (None, None) => None,
+                };
+            }
+            Ok(StageOutcome::Memory(memory)) => {
+                if memory.inconclusive() {
+                    report.outcome = "inconclusive".to_string();
+                } else if !memory.passed() {
+                    report.outcome = "failed".to_string();
+                }
+                report.detail = Some(format!(
+                    "{} MB, {} passes, {} bad bits",
+                    memory.bytes_tested / 1024 / 1024,
+                    memory.passes,
+                    memory.error_count
+                ));
+            }
+            Ok(StageOutcome::Bandwidth(bandwidth)) => {
+                let total = bandwidth.total;
+                report.rate = Some(total.average());
+                report.rate_unit = Some("GB/s".to_string());
+                report.thread_rates = bandwidth
+                    .threads
+                    .iter()
+                    .map(|thread| thread.rates.average())
+                    .collect();
+                report.detail = Some(match bandwidth.errors.first() {
+                    Some(error) => {
+                        report.outcome = "failed".to_string();
+                        error.clone()
+                    }
+                    None => format!(
+                        "copy {:.1}, scale {:.1}, add {:.1}, triad {:.1} GB/s; per thread {}",
+                        total.copy,
+                        total.scale,
+                        total.add,
+                        total.triad,
+                        report
+                            .thread_rates
+                            .iter()
+                            .map(|rate| format!("{:.1}", rate))
+                            .collect::<Vec<_>>()
+                            .join(" / ")
+                    ),
+                });
+            }
+            Err(e) => report.outcome = e,
+        }
+        report
+    }
+
+    /// A stage that never ran because the plan stopped before it.
+    pub fn skipped(stage: &Stage) -> Self {
+        StageReport {
+            name: stage.name.clone(),
+            kind: stage.kind().to_string(),
+            start_secs: 0.0,
+            elapsed_secs: 0.0,
+            outcome: "skipped".to_string(),
+            score: None,
+            rate: None,
+            rate_unit: None,
+            thread_rates: Vec::new(),
+            detail: None,
+            average_usage: None,
+            average_temperature: None,
+            peak_temperature: None,
+            average_frequency: None,
+        }
+    }
+
+    /// Marks a stage the user cut short. One that already failed keeps its outcome.
+    pub fn stop(&mut self) {
+        if self.passed() {
+            self.outcome = "stopped".to_string();
+        }
+    }
+
+    /// Whether the stage ran without a problem.
+    pub fn passed(&self) -> bool {
+        self.outcome == "passed" || self.outcome == "done"
+    }
+}
+
+/// Progress of a plan that is running.
+pub struct PlanRun {
+    pub plan: TestPlan,
+    pub started_at: DateTime<Utc>,
+    /// Index of the running stage.
+    pub current: usize,
+    /// Seconds into the plan at which the running stage started.
+    pub stage_start: f64,
+    pub reports: Vec<StageReport>,
+    /// Stopped by the user; the remaining stages are skipped.
+    pub stopped: bool,
+}
+
+impl PlanRun {
+    pub fn new(plan: TestPlan) -> Self {
+        PlanRun {
+            plan,
+            started_at: Utc::now(),
+            current: 0,
+            stage_start: 0.0,
+            reports: Vec::new(),
+            stopped: false,
+        }
+    }
+
+    pub fn stage(&self) -> Option<&Stage> {
+        self.plan.stages.get(self.current)
+    }
+
+    /// Builds the combined report, listing the stages that did not get to run as
+    /// skipped.
+    pub fn finish(mut self, machine: HardwareInfo, samples: Vec<RunSample>) -> PlanReport {
+        for stage in &self.plan.stages[self.reports.len()..] {
+            self.reports.push(StageReport::skipped(stage));
+        }
+        let outcome = if self
+            .reports
+            .iter()
+            .any(|r| r.outcome.starts_with("aborted"))
+        {
+            "aborted: thermal limit"
+        } else if self.stopped {
+            "stopped"
+        } else if self.reports.iter().all(StageReport::passed) {
+            "passed"
+        } else {
+            "failed"
+        };
+        let scaling = if self.plan.sweep {
+            ScalingReport::new(&self.plan, &self.reports, machine.physical_cores)
+        } else {
+            None
+        };
+        PlanReport {
+            name: self.plan.name,
+            file: self.plan.path,
+            started_at: self.started_at,
+            finished_at: Utc::now(),
+            machine: machine.into(),
+            outcome: outcome.to_string(),
+            stages: self.reports,
+            scaling,
+            samples,
+        }
+    }
+}
+
+/// The combined report of a whole plan, with the time series of all its stages.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PlanReport {
+    pub name: String,
+    pub file: PathBuf,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub machine: Machine,
+    /// "passed" when every stage passed, otherwise "failed", "stopped" or
+    /// "aborted: thermal limit".
+    pub outcome: String,
+    pub stages: Vec<StageReport>,
+    /// Throughput versus threads, for core-scaling sweeps.
+    pub scaling: Option<ScalingReport>,
+    pub samples: Vec<RunSample>,
+}
+
+impl PlanReport {
+    /// Writes the report as `<dir>/<start time>-<plan name>.json`, with the time series
+    /// next to it as a `.csv`. Returns the JSON path.
+    pub fn save(&self, dir: &Path) -> Result<PathBuf, String> {
+        let label: String = self
+            .name
+            .chars()
+            .map(|c| {
+                if c.is_alphanumeric() {
+                    c.to_ascii_lowercase()
+                } else {
+                    '-'
+                }
+            })
+            .collect();
+        save_with_samples(self, &self.samples, dir, self.started_at, &label)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stage(toml: &str) -> StageFile {
+        toml::from_str(toml).unwrap()
+    }
+
+    fn expand(toml: &str) -> Result<Vec<Stage>, String> {
+        expand_stage(&stage(toml), &WorkloadRegistry::new(), 4, None)
+    }
+
+    /// Writes `text` to a plan file of its own in the temp directory and loads it.
+    fn load(name: &str, text: &str) -> Result<TestPlan, String> {
+        let path =
+            std::env::temp_dir().join(format!("md_plan_test_{}_{}.toml", std::process::id(), name));
+        fs::write(&path, text).unwrap();
+        let plan = TestPlan::load(&path, &WorkloadRegistry::new(), 4);
+        fs::remove_file(&path).unwrap();
+        plan
+    }
+
+    fn cpu_config(stage: &Stage) -> &StressConfig {
+        match &stage.task {
+            StageTask::Cpu(config) => config,
+            task => panic!("{:?} is not a CPU stage", task),
+        }
+    }
+
+    #[test]
+    fn loads_the_example_plan() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("plans/qualification.toml");
+        let plan = TestPlan::load(&path, &WorkloadRegistry::new(), 4).unwrap();
+        assert_eq!(plan.name, "Qualification");
+        assert!(!plan.sweep);
+        let kinds: Vec<&str> = plan.stages.iter().map(Stage::kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                "idle",
+                "cpu",
+                "cpu",
+                "cpu",
+                "cpu",
+                "cpu",
+                "bandwidth",
+                "memory",
+                "idle"
+            ]
+        );
+        assert_eq!(plan.duration_secs(), 60 + 4 * 120 + 600 + 60 + 300 + 180);
+        for stage in &plan.stages[1..6] {
+            assert_eq!(
+                cpu_config(stage).thermal_limit,
+                Some(ThermalLimit::new(95.0))
+            );
+        }
+    }
+
+    #[test]
+    fn plan_name_defaults_to_the_file_name() {
+        let plan = load("unnamed", "[[stage]]\nkind = \"idle\"\nduration = \"1s\"\n").unwrap();
+        assert_eq!(
+            plan.name,
+            format!("md_plan_test_{}_unnamed", std::process::id())
+        );
+    }
+
+    #[test]
+    fn load_rejects_bad_fields() {
+        let cases = [
+            ("no_stages", "name = \"Empty\"", "has no stages"),
+            (
+                "unknown_field",
+                "[[stage]]\nkind = \"idle\"\nduration = \"1s\"\nspeed = 3",
+                "unknown field",
+            ),
+            (
+                "unknown_kind",
+                "[[stage]]\nkind = \"gpu\"\nduration = \"1s\"",
+                "unknown variant",
+            ),
+            (
+                "bad_limit",
+                "thermal_limit = \"hot\"\n[[stage]]\nkind = \"idle\"\nduration = \"1s\"",
+                "Invalid temperature",
+            ),
+            (
+                "bad_duration",
+                "[[stage]]\nkind = \"idle\"\nduration = \"soon\"",
+                "Invalid duration",
+            ),
+            (
+                "zero_duration",
+                "[[stage]]\nkind = \"idle\"\nduration = \"0s\"",
+                "Invalid duration",
+            ),
+            (
+                "bad_workload",
+                "[[stage]]\nkind = \"cpu\"\nduration = \"1s\"\nworkload = \"nope\"",
+                "Unknown workload",
+            ),
+            (
+                "zero_threads",
+                "[[stage]]\nkind = \"cpu\"\nduration = \"1s\"\nthreads = 0",
+                "at least 1",
+            ),
+            (
+                "missing_cpu",
+                "[[stage]]\nkind = \"cpu\"\nduration = \"1s\"\naffinity = \"7\"",
+                "does not exist",
+            ),
+            (
+                "zero_load",
+                "[[stage]]\nkind = \"cpu\"\nduration = \"1s\"\ntarget_load = 0.0",
+                "Target load",
+            ),
+            (
+                "high_load",
+                "[[stage]]\nkind = \"cpu\"\nduration = \"1s\"\ntarget_load = 150.0",
+                "Target load",
+            ),
+            (
+                "big_share",
+                "[[stage]]\nkind = \"memory\"\nduration = \"1s\"\nshare = 1.5",
+                "Memory share",
+            ),
+        ];
+        for (name, text, error) in cases {
+            match load(name, text) {
+                Ok(_) => panic!("{} loaded", name),
+                Err(e) => assert!(e.contains(error), "{}: {}", name, e),
+            }
+        }
+    }
+
+    #[test]
+    fn errors_name_the_stage() {
+        let text = "[[stage]]\nkind = \"idle\"\nduration = \"1s\"\n\
+                    [[stage]]\nkind = \"cpu\"\nduration = \"1s\"\ntarget_load = 101.0\n";
+        let error = load("stage_number", text).unwrap_err();
+        assert!(error.starts_with("Stage 2: "), "{}", error);
+    }
+
+    #[test]
+    fn cpu_stage_defaults() {
+        let stages = expand("kind = \"cpu\"\nduration = \"90s\"").unwrap();
+        assert_eq!(stages.len(), 1);
+        assert_eq!(stages[0].name, "fibonacci on 4 threads");
+        assert_eq!(stages[0].duration_secs, 90);
+        let config = cpu_config(&stages[0]);
+        assert_eq!(config.threads, 4);
+        assert_eq!(config.workload, "fibonacci");
+        assert_eq!(config.affinity, CpuAffinity::Unpinned);
+        assert_eq!(config.target_load, None);
+        assert_eq!(config.thermal_limit, None);
+    }
+
+    #[test]
+    fn cpu_stage_options() {
+        let toml = "kind = \"cpu\"\nname = \"Half load\"\nduration = \"1.5s\"\n\
+                    workload = \"fib\"\naffinity = \"1-2\"\ntarget_load = 50.0";
+        let limit = ThermalLimit::new(90.0);
+        let stages = expand_stage(&stage(toml), &WorkloadRegistry::new(), 4, Some(&limit)).unwrap();
+        assert_eq!(stages[0].name, "Half load");
+        // Partial seconds round up
+        assert_eq!(stages[0].duration_secs, 2);
+        let config = cpu_config(&stages[0]);
+        // Pinned stages get one thread per listed CPU
+        assert_eq!(config.threads, 2);
+        assert_eq!(config.affinity, CpuAffinity::Cpus(vec![1, 2]));
+        assert_eq!(config.target_load, Some(50.0));
+        assert_eq!(config.thermal_limit, Some(limit));
+    }
+
+    #[test]
+    fn single_core_runs_once_per_cpu() {
+        let stages = expand("kind = \"single-core\"\nduration = \"1m\"").unwrap();
+        assert_eq!(stages.len(), 4);
+        for (cpu, stage) in stages.iter().enumerate() {
+            assert_eq!(stage.name, format!("fibonacci single core, CPU {}", cpu));
+            assert_eq!(stage.duration_secs, 60);
+            let config = cpu_config(stage);
+            assert_eq!(config.threads, 1);
+            assert_eq!(config.affinity, CpuAffinity::Cpus(vec![cpu]));
+        }
+
+        let stages =
+            expand("kind = \"single-core\"\nduration = \"1m\"\naffinity = \"1,3\"").unwrap();
+        let cpus: Vec<&CpuAffinity> = stages.iter().map(|s| &cpu_config(s).affinity).collect();
+        assert_eq!(
+            cpus,
+            [&CpuAffinity::Cpus(vec![1]), &CpuAffinity::Cpus(vec![3])]
+        );
+    }
+
+    #[test]
+    fn memory_and_bandwidth_defaults() {
+        let stages = expand("kind = \"memory\"\nduration = \"1m\"").unwrap();
+        assert_eq!(stages[0].name, "Memory test");
+        assert!(matches!(
+            stages[0].task,
+            StageTask::Memory {
+                share: DEFAULT_MEMORY_SHARE,
+                threads: 4
+            }
+        ));
+
+        let stages = expand("kind = \"bandwidth\"\nduration = \"1m\"\nthreads = 2").unwrap();
+        assert_eq!(stages[0].name, "Memory bandwidth on 2 threads");
+        assert!(matches!(
+            stages[0].task,
+            StageTask::Bandwidth { threads: 2 }
+        ));
+    }
+
+    #[test]
+    fn cooldown_is_idle() {
+        let stages = expand("kind = \"cooldown\"\nduration = \"3m\"").unwrap();
+        assert_eq!(stages[0].name, "Idle");
+        assert!(matches!(stages[0].task, StageTask::Idle));
+    }
+}
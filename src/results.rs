@@ -0,0 +1,675 @@
+use std::{
+    fmt::Write as _,
+    fs,
+    io::{self, Write as _},
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Local, Utc};
+use md_hardware::{CpuUsage, HardwareInfo, StressConfig, StressResult, ThroughputSample};
+use serde::{Deserialize, Serialize};
+
+use crate::timeseries::TimeSeries;
+
+/// Where finished runs are saved, relative to the working directory.
+pub const RESULTS_DIR: &str = "results";
+
+/// Everything kept about a finished run: how it was set up, what it scored, the
+/// machine it ran on and what was sampled along the way.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub machine: Machine,
+    pub settings: RunSettings,
+    /// "passed", "failed" or "aborted: thermal limit".
+    pub outcome: String,
+    pub score: u64,
+    pub rate: f64,
+    pub rate_unit: String,
+    pub elapsed_secs: f64,
+    pub workers: Vec<WorkerScore>,
+    pub errors: Vec<RecordedError>,
+    pub thermal_abort: Option<String>,
+    /// Hottest sensor reading seen during the run, in °C.
+    pub peak_temperature: Option<f32>,
+    pub throttling: Throttling,
+    pub samples: Vec<RunSample>,
+}
+
+/// The hardware fingerprint of a record.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Machine {
+    pub hostname: String,
+    pub os: String,
+    pub kernel: String,
+    pub arch: String,
+    pub cpu_brand: String,
+    pub cpu_vendor: String,
+    pub logical_cores: usize,
+    pub physical_cores: Option<usize>,
+    pub total_memory: u64,
+    pub max_frequency: Option<u64>,
+}
+
+impl From<HardwareInfo> for Machine {
+    fn from(info: HardwareInfo) -> Self {
+        Machine {
+            hostname: info.hostname,
+            os: info.os,
+            kernel: info.kernel,
+            arch: info.arch,
+            cpu_brand: info.cpu_brand,
+            cpu_vendor: info.cpu_vendor,
+            logical_cores: info.logical_cores,
+            physical_cores: info.physical_cores,
+            total_memory: info.total_memory,
+            max_frequency: info.max_frequency,
+        }
+    }
+}
+
+/// How the run was configured. Free-text options are kept as they were typed.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RunSettings {
+    pub duration_secs: u64,
+    pub threads: usize,
+    pub workload: String,
+    pub affinity: String,
+    pub target_load: Option<f32>,
+    pub profile: Option<String>,
+    pub thermal_limit: Option<String>,
+}
+
+impl RunSettings {
+    pub fn new(config: &StressConfig, affinity: &str, profile: &str, thermal_limit: &str) -> Self {
+        let text = |s: &str| (!s.trim().is_empty()).then(|| s.trim().to_string());
+        RunSettings {
+            duration_secs: config.duration_sec,
+            threads: config.threads,
+            workload: config.workload.clone(),
+            affinity: text(affinity).unwrap_or_else(|| "unpinned".to_string()),
+            target_load: config.target_load,
+            profile: text(profile),
+            thermal_limit: text(thermal_limit),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WorkerScore {
+    pub thread_index: usize,
+    pub pinned_cpu: Option<usize>,
+    pub core: Option<usize>,
+    pub score: u64,
+    pub rate: f64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecordedError {
+    pub thread_index: usize,
+    pub core: Option<usize>,
+    pub at_secs: f64,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Throttling {
+    pub throttled_secs: f64,
+    pub first_at_secs: Option<f64>,
+    pub frequency_drops: u32,
+    pub rate_drops: u32,
+    pub average_frequency: Option<f64>,
+    pub baseline_frequency: Option<f64>,
+    pub lowest_frequency: Option<f64>,
+}
+
+/// One row of the sampled time series, taken once per second of the run.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RunSample {
+    pub elapsed: f64,
+    /// Average usage of all logical CPUs in percent.
+    pub usage: f64,
+    /// Usage of every logical CPU in percent.
+    pub cores: Vec<f32>,
+    /// Used RAM in bytes.
+    pub ram_used: u64,
+    /// Hottest sensor in °C.
+    pub temperature: Option<f32>,
+    /// Workload rate over the latest sampling interval.
+    pub throughput: Option<f64>,
+    /// Mean frequency of the workers' CPUs in MHz.
+    pub frequency: Option<f64>,
+    /// Frequency of every logical CPU in MHz, 0 where unknown.
+    #[serde(default)]
+    pub core_frequencies: Vec<u64>,
+}
+
+impl RunSample {
+    /// A row from the current CPU readings, with the workload figures of the latest
+    /// throughput sample.
+    pub fn new(
+        elapsed: f64,
+        cpus: &[CpuUsage],
+        ram_used: u64,
+        temperature: Option<f32>,
+        latest: Option<&ThroughputSample>,
+    ) -> Self {
+        let usage = cpus.iter().map(|cpu| cpu.usage as f64).sum::<f64>() / cpus.len().max(1) as f64;
+        RunSample {
+            elapsed,
+            usage,
+            cores: cpus.iter().map(|cpu| cpu.usage).collect(),
+            ram_used,
+            temperature,
+            throughput: latest.map(|sample| sample.rate),
+            frequency: latest.and_then(|sample| sample.frequency),
+            core_frequencies: latest.map_or_else(Vec::new, |sample| sample.frequencies.clone()),
+        }
+    }
+}
+
+/// Rows kept per run before neighbouring rows are merged, over an hour at one per second.
+const SAMPLE_CAPACITY: usize = 4096;
+
+/// The samples of a run in bounded memory, merged the way `TimeSeries` merges its
+/// buckets: once the capacity is reached, pairs of rows are combined and new rows take
+/// twice as many samples. Merged rows keep the weighted averages of their samples and
+/// the peak temperature and RAM, so short spikes survive in saved runs.
+#[derive(Clone, Debug)]
+pub struct RunSamples {
+    rows: Vec<RunSample>,
+    /// Samples combined into each row.
+    counts: Vec<u32>,
+    samples_per_row: u32,
+}
+
+impl RunSamples {
+    pub fn new() -> Self {
+        RunSamples {
+            rows: Vec::new(),
+            counts: Vec::new(),
+            samples_per_row: 1,
+        }
+    }
+
+    /// Appends a sample. Times must not go backwards.
+    pub fn push(&mut self, sample: RunSample) {
+        if let (Some(last), Some(count)) = (self.rows.last_mut(), self.counts.last_mut())
+            && *count < self.samples_per_row
+        {
+            last.merge(*count, &sample, 1);
+            *count += 1;
+            return;
+        }
+        self.rows.push(sample);
+        self.counts.push(1);
+        if self.rows.len() > SAMPLE_CAPACITY {
+            self.compact();
+        }
+    }
+
+    /// Merges neighbouring rows, halving the resolution.
+    fn compact(&mut self) {
+        let rows = std::mem::take(&mut self.rows);
+        let counts = std::mem::take(&mut self.counts);
+        let mut pairs = rows.into_iter().zip(counts);
+        while let Some((mut row, mut count)) = pairs.next() {
+            if let Some((next, next_count)) = pairs.next() {
+                row.merge(count, &next, next_count);
+                count += next_count;
+            }
+            self.rows.push(row);
+            self.counts.push(count);
+        }
+        self.samples_per_row *= 2;
+    }
+
+    pub fn clear(&mut self) {
+        *self = RunSamples::new();
+    }
+
+    pub fn as_slice(&self) -> &[RunSample] {
+        &self.rows
+    }
+
+    /// Hands over the rows, leaving the series empty.
+    pub fn take(&mut self) -> Vec<RunSample> {
+        std::mem::replace(self, RunSamples::new()).rows
+    }
+}
+
+impl RunSample {
+    /// Folds `other` into this row, weighting each by how many samples it stands for.
+    /// The row keeps its own time, like the start of a `TimeSeries` bucket.
+    fn merge(&mut self, count: u32, other: &RunSample, other_count: u32) {
+        let weight = count as f64 / (count + other_count) as f64;
+        let mean = |a: f64, b: f64| a * weight + b * (1.0 - weight);
+        let mean_option = |a: Option<f64>, b: Option<f64>| match (a, b) {
+            (Some(a), Some(b)) => Some(mean(a, b)),
+            (a, b) => a.or(b),
+        };
+        self.usage = mean(self.usage, other.usage);
+        for (core, &other) in self.cores.iter_mut().zip(&other.cores) {
+            *core = mean(*core as f64, other as f64) as f32;
+        }
+        self.ram_used = self.ram_used.max(other.ram_used);
+        self.temperature = match (self.temperature, other.temperature) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        };
+        self.throughput = mean_option(self.throughput, other.throughput);
+        self.frequency = mean_option(self.frequency, other.frequency);
+        for (frequency, &other) in self
+            .core_frequencies
+            .iter_mut()
+            .zip(&other.core_frequencies)
+        {
+            *frequency = mean(*frequency as f64, other as f64).round() as u64;
+        }
+    }
+}
+
+impl RunRecord {
+    pub fn new(
+        result: &StressResult,
+        settings: RunSettings,
+        machine: HardwareInfo,
+        samples: Vec<RunSample>,
+    ) -> Self {
+        let throttling = &result.throttling;
+        RunRecord {
+            started_at: result.started_at.into(),
+            finished_at: result.finished_at.into(),
+            machine: machine.into(),
+            settings,
+            outcome: result.outcome().to_string(),
+            score: result.score,
+            rate: result.rate,
+            rate_unit: result.rate_unit.to_string(),
+            elapsed_secs: result.elapsed.as_secs_f64(),
+            workers: result
+                .workers
+                .iter()
+                .map(|w| WorkerScore {
+                    thread_index: w.thread_index,
+                    pinned_cpu: w.pinned_cpu,
+                    core: w.core,
+                    score: w.score,
+                    rate: w.rate,
+                })
+                .collect(),
+            errors: result
+                .errors
+                .iter()
+                .map(|e| RecordedError {
+                    thread_index: e.thread_index,
+                    core: e.core,
+                    at_secs: e.at.as_secs_f64(),
+                    message: e.message.clone(),
+                })
+                .collect(),
+            thermal_abort: result.thermal_abort.as_ref().map(|abort| {
+                format!(
+                    "{} {:.1}°C at {:.0}s, {}",
+                    abort.sensor,
+                    abort.temperature,
+                    abort.at.as_secs_f64(),
+                    abort.rule
+                )
+            }),
+            peak_temperature: result.peak_temperature,
+            throttling: Throttling {
+                throttled_secs: throttling.throttled_for.as_secs_f64(),
+                first_at_secs: throttling.first_at.map(|at| at.as_secs_f64()),
+                frequency_drops: throttling.frequency_drops,
+                rate_drops: throttling.rate_drops,
+                average_frequency: throttling.average_frequency,
+                baseline_frequency: throttling.baseline_frequency,
+                lowest_frequency: throttling.lowest_frequency,
+            },
+            samples,
+        }
+    }
+
+    /// Writes the record as `<dir>/<start time>-<workload>.json`, with the time
+    /// series next to it as a `.csv` of the same name. Returns the JSON path.
+    pub fn save(&self, dir: &Path) -> Result<PathBuf, String> {
+        save_with_samples(
+            self,
+            &self.samples,
+            dir,
+            self.started_at,
+            &self.settings.workload,
+        )
+    }
+
+    /// Writes the record as JSON to `path` and the time series as CSV next to it,
+    /// creating the directory if needed.
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        write_with_samples(self, &self.samples, path)
+    }
+
+    /// One value of the sampled time series over the run, skipping samples without it.
+    pub fn series(&self, value: impl Fn(&RunSample) -> Option<f64>) -> TimeSeries {
+        let mut series = TimeSeries::new();
+        for sample in &self.samples {
+            if let Some(value) = value(sample) {
+                series.push(sample.elapsed, value);
+            }
+        }
+        series
+    }
+
+    /// Reads a record written by `save`.
+    pub fn load(path: &Path) -> Result<RunRecord, String> {
+        let json = fs::read_to_string(path)
+            .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+        serde_json::from_str(&json).map_err(|e| format!("Invalid record {}: {}", path.display(), e))
+    }
+}
+
+/// Name for a saved file without its extension: the local start time to the
+/// millisecond followed by `label`, e.g. "20250101-120000-250-fibonacci".
+pub fn file_stem(started_at: DateTime<Utc>, label: &str) -> String {
+    format!(
+        "{}-{}",
+        started_at.with_timezone(&Local).format("%Y%m%d-%H%M%S-%3f"),
+        label
+    )
+}
+
+/// Writes `value` as JSON to `path` and `samples` as CSV next to it, creating the
+/// directory if needed. Existing files are replaced.
+pub fn write_with_samples(
+    value: &impl Serialize,
+    samples: &[RunSample],
+    path: &Path,
+) -> Result<(), String> {
+    if let Some(dir) = path.parent()
+        && !dir.as_os_str().is_empty()
+    {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Could not create {}: {}", dir.display(), e))?;
+    }
+    let json = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+    fs::write(path, json).map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+    let csv_path = path.with_extension("csv");
+    fs::write(&csv_path, samples_csv(samples))
+        .map_err(|e| format!("Could not write {}: {}", csv_path.display(), e))
+}
+
+/// Saves `value` as JSON and `samples` as CSV under a new name in `dir`, see
+/// `file_stem`. Never replaces a file: when the name is taken, "-2", "-3" and so on
+/// is appended. Returns the JSON path.
+pub fn save_with_samples(
+    value: &impl Serialize,
+    samples: &[RunSample],
+    dir: &Path,
+    started_at: DateTime<Utc>,
+    label: &str,
+) -> Result<PathBuf, String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Could not create {}: {}", dir.display(), e))?;
+    let json = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+    let stem = file_stem(started_at, label);
+    let mut attempt = 1;
+    let path = loop {
+        let name = match attempt {
+            1 => stem.clone(),
+            n => format!("{}-{}", stem, n),
+        };
+        attempt += 1;
+        let path = dir.join(format!("{}.json", name));
+        if path.with_extension("csv").exists() {
+            continue;
+        }
+        match create_new(&path, &json) {
+            Ok(()) => break path,
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(format!("Could not write {}: {}", path.display(), e)),
+        }
+    };
+    let csv_path = path.with_extension("csv");
+    create_new(&csv_path, &samples_csv(samples))
+        .map_err(|e| format!("Could not write {}: {}", csv_path.display(), e))?;
+    Ok(path)
+}
+
+/// Writes `contents` to a file that must not exist yet.
+fn create_new(path: &Path, contents: &str) -> io::Result<()> {
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)?
+        .write_all(contents.as_bytes())
+}
+
+/// The sampled time series, one row per sample with a usage and a frequency column
+/// per CPU.
+pub fn samples_csv(samples: &[RunSample]) -> String {
+    let cores = samples.iter().map(|s| s.cores.len()).max().unwrap_or(0);
+    let frequency_cores = samples
+        .iter()
+        .map(|s| s.core_frequencies.len())
+        .max()
+        .unwrap_or(0);
+    let mut csv =
+        String::from("elapsed_s,usage_pct,ram_used_mb,temperature_c,throughput,frequency_mhz");
+    for i in 0..cores {
+        let _ = write!(csv, ",cpu{}_pct", i);
+    }
+    for i in 0..frequency_cores {
+        let _ = write!(csv, ",cpu{}_mhz", i);
+    }
+    csv.push('\n');
+
+    let optional = |value: Option<String>| value.unwrap_or_default();
+    for sample in samples {
+        let _ = write!(
+            csv,
+            "{},{:.1},{},{},{},{}",
+            sample.elapsed,
+            sample.usage,
+            sample.ram_used / 1024 / 1024,
+            optional(sample.temperature.map(|t| format!("{:.1}", t))),
+            optional(sample.throughput.map(|r| format!("{:.3}", r))),
+            optional(sample.frequency.map(|f| format!("{:.0}", f))),
+        );
+        for i in 0..cores {
+            let usage = sample.cores.get(i).map(|usage| format!("{:.1}", usage));
+            let _ = write!(csv, ",{}", optional(usage));
+        }
+        for i in 0..frequency_cores {
+            let frequency = sample.core_frequencies.get(i).filter(|&&f| f > 0);
+            let _ = write!(csv, ",{}", optional(frequency.map(u64::to_string)));
+        }
+        csv.push('\n');
+    }
+    csv
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// A finished run of `workload` with two samples, for tests that need records.
+    pub fn record(workload: &str, score: u64, started_at: DateTime<Utc>) -> RunRecord {
+        RunRecord {
+            started_at,
+            finished_at: started_at + chrono::Duration::seconds(60),
+            machine: Machine {
+                hostname: "bench".to_string(),
+                os: "Linux".to_string(),
+                kernel: "6.1".to_string(),
+                arch: "x86_64".to_string(),
+                cpu_brand: "Test CPU".to_string(),
+                cpu_vendor: "Test".to_string(),
+                logical_cores: 2,
+                physical_cores: Some(1),
+                total_memory: 8 << 30,
+                max_frequency: Some(3000),
+            },
+            settings: RunSettings {
+                duration_secs: 60,
+                threads: 2,
+                workload: workload.to_string(),
+                affinity: "unpinned".to_string(),
+                target_load: None,
+                profile: None,
+                thermal_limit: None,
+            },
+            outcome: "passed".to_string(),
+            score,
+            rate: score as f64 / 60.0,
+            rate_unit: "units/s".to_string(),
+            elapsed_secs: 60.0,
+            workers: vec![WorkerScore {
+                thread_index: 0,
+                pinned_cpu: None,
+                core: Some(1),
+                score,
+                rate: score as f64 / 60.0,
+            }],
+            errors: Vec::new(),
+            thermal_abort: None,
+            peak_temperature: Some(70.0),
+            throttling: Throttling::default(),
+            samples: vec![
+                sample(1.0, &[100.0, 50.0], &[3000, 0]),
+                sample(2.0, &[99.5], &[]),
+            ],
+        }
+    }
+
+    fn sample(elapsed: f64, cores: &[f32], core_frequencies: &[u64]) -> RunSample {
+        RunSample {
+            elapsed,
+            usage: cores.iter().sum::<f32>() as f64 / cores.len() as f64,
+            cores: cores.to_vec(),
+            ram_used: 3 << 20,
+            temperature: (elapsed < 2.0).then_some(65.0),
+            throughput: Some(12.3456),
+            frequency: core_frequencies.first().map(|&f| f as f64),
+            core_frequencies: core_frequencies.to_vec(),
+        }
+    }
+
+    /// An empty directory of its own in the temp directory.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("md_results_test_{}_{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn csv_has_a_column_per_cpu() {
+        let csv = samples_csv(&record("fibonacci", 100, Utc::now()).samples);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "elapsed_s,usage_pct,ram_used_mb,temperature_c,throughput,frequency_mhz,\
+                 cpu0_pct,cpu1_pct,cpu0_mhz,cpu1_mhz",
+                "1,75.0,3,65.0,12.346,3000,100.0,50.0,3000,",
+                // Missing values are left empty
+                "2,99.5,3,,12.346,,99.5,,,",
+            ]
+        );
+    }
+
+    #[test]
+    fn csv_without_samples_is_only_the_header() {
+        assert_eq!(
+            samples_csv(&[]),
+            "elapsed_s,usage_pct,ram_used_mb,temperature_c,throughput,frequency_mhz\n"
+        );
+    }
+
+    #[test]
+    fn saved_records_load_back() {
+        let dir = temp_dir("load_back");
+        let record = record("fibonacci", 100, Utc::now());
+        let path = record.save(&dir).unwrap();
+        assert_eq!(
+            path.file_name().unwrap().to_string_lossy(),
+            format!("{}.json", file_stem(record.started_at, "fibonacci"))
+        );
+
+        let loaded = RunRecord::load(&path).unwrap();
+        assert_eq!(
+            serde_json::to_value(&loaded).unwrap(),
+            serde_json::to_value(&record).unwrap()
+        );
+        let csv = fs::read_to_string(path.with_extension("csv")).unwrap();
+        assert_eq!(csv, samples_csv(&record.samples));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn saving_never_replaces_a_file() {
+        let dir = temp_dir("unique");
+        let started_at = Utc::now();
+        let first = record("fibonacci", 100, started_at).save(&dir).unwrap();
+        let second = record("fibonacci", 200, started_at).save(&dir).unwrap();
+        let stem = file_stem(started_at, "fibonacci");
+        assert_eq!(first, dir.join(format!("{}.json", stem)));
+        assert_eq!(second, dir.join(format!("{}-2.json", stem)));
+        assert_eq!(RunRecord::load(&first).unwrap().score, 100);
+        assert_eq!(RunRecord::load(&second).unwrap().score, 200);
+        assert!(second.with_extension("csv").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn run_samples_stay_bounded_over_a_long_run() {
+        let mut samples = RunSamples::new();
+        let total = SAMPLE_CAPACITY * 3;
+        for second in 0..total {
+            // Alternating idle and busy seconds, with a single hot one in the middle
+            let usage = if second % 2 == 0 { 0.0 } else { 100.0 };
+            let temperature = if second == total / 2 { 95.0 } else { 60.0 };
+            samples.push(RunSample {
+                usage,
+                temperature: Some(temperature),
+                ..sample(second as f64, &[usage as f32], &[3000])
+            });
+        }
+
+        let rows = samples.as_slice();
+        assert!(rows.len() <= SAMPLE_CAPACITY, "{} rows", rows.len());
+        assert_eq!(samples.counts.iter().sum::<u32>() as usize, total);
+        assert_eq!(rows[0].elapsed, 0.0);
+        assert!(
+            rows.windows(2)
+                .all(|pair| pair[0].elapsed < pair[1].elapsed)
+        );
+        assert!(
+            rows.iter()
+                .all(|row| row.usage == 50.0 && row.cores == [50.0])
+        );
+        assert!(rows.iter().all(|row| row.core_frequencies == [3000]));
+        let peak = rows
+            .iter()
+            .filter_map(|row| row.temperature)
+            .reduce(f32::max);
+        assert_eq!(peak, Some(95.0));
+
+        let row_count = rows.len();
+        assert_eq!(samples.take().len(), row_count);
+        assert!(samples.as_slice().is_empty());
+    }
+
+    #[test]
+    fn short_runs_keep_every_sample() {
+        let mut samples = RunSamples::new();
+        let record = record("fibonacci", 100, Utc::now());
+        for sample in &record.samples {
+            samples.push(sample.clone());
+        }
+        assert_eq!(
+            samples_csv(samples.as_slice()),
+            samples_csv(&record.samples)
+        );
+    }
+}
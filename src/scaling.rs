@@ -0,0 +1,221 @@
+use std::path::PathBuf;
+
+use md_hardware::{StressConfig, ThermalLimit};
+use serde::{Deserialize, Serialize};
+
+use crate::plan::{Stage, StageReport, StageTask, TestPlan};
+
+/// Below this share of the single-thread rate, an added thread is counted as
+/// adding less, e.g. an SMT sibling of an already busy core.
+const KNEE_EFFICIENCY: f64 = 0.5;
+
+/// Thread counts of a sweep: powers of two up to `logical_cores`, which is always
+/// the last step.
+pub fn sweep_threads(logical_cores: usize) -> Vec<usize> {
+    let mut threads: Vec<usize> = std::iter::successors(Some(1), |&n| Some(n * 2))
+        .take_while(|&n| n < logical_cores)
+        .collect();
+    threads.push(logical_cores.max(1));
+    threads
+}
+
+/// A plan running `workload` for `step_secs` on every thread count of the sweep.
+pub fn sweep_plan(
+    workload: &str,
+    step_secs: u64,
+    logical_cores: usize,
+    thermal_limit: Option<ThermalLimit>,
+) -> TestPlan {
+    let stages = sweep_threads(logical_cores)
+        .into_iter()
+        .map(|threads| {
+            let mut config = StressConfig::new(step_secs, threads, workload);
+            if let Some(limit) = &thermal_limit {
+                config = config.with_thermal_limit(limit.clone());
+            }
+            Stage {
+                name: format!("{} thread{}", threads, if threads == 1 { "" } else { "s" }),
+                duration_secs: step_secs,
+                task: StageTask::Cpu(config),
+            }
+        })
+        .collect();
+    TestPlan {
+        name: format!("Core scaling {}", workload),
+        path: PathBuf::new(),
+        stages,
+        sweep: true,
+    }
+}
+
+/// One thread count of a sweep.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ScalingStep {
+    pub threads: usize,
+    pub rate: f64,
+    /// Rate relative to a single thread.
+    pub speedup: f64,
+    /// Speedup per thread; 1.0 is perfect scaling.
+    pub efficiency: f64,
+    /// What each thread added since the previous step, relative to a single thread.
+    pub added_per_thread: f64,
+}
+
+/// Throughput versus thread count, worked out from the stages of a sweep.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ScalingReport {
+    pub rate_unit: String,
+    pub physical_cores: Option<usize>,
+    pub steps: Vec<ScalingStep>,
+    /// Thread count after which more threads start adding clearly less, typically
+    /// where SMT siblings of busy cores come in.
+    pub knee: Option<usize>,
+}
+
+impl ScalingReport {
+    /// Reads the rates of the passed stages. Needs the single-thread step as the
+    /// baseline.
+    pub fn new(
+        plan: &TestPlan,
+        reports: &[StageReport],
+        physical_cores: Option<usize>,
+    ) -> Option<Self> {
+        let measured: Vec<(usize, f64)> = plan
+            .stages
+            .iter()
+            .zip(reports)
+            .filter(|(_, report)| report.passed())
+            .filter_map(|(stage, report)| match &stage.task {
+                StageTask::Cpu(config) => Some((config.threads, report.rate?)),
+                _ => None,
+            })
+            .collect();
+        let &(1, single) = measured.first()? else {
+            return None;
+        };
+        if single <= 0.0 {
+            return None;
+        }
+
+        let mut steps = Vec::new();
+        let mut previous = (0, 0.0);
+        for (threads, rate) in measured {
+            let speedup = rate / single;
+            steps.push(ScalingStep {
+                threads,
+                rate,
+                speedup,
+                efficiency: speedup / threads as f64,
+                added_per_thread: (rate - previous.1) / (threads - previous.0) as f64 / single,
+            });
+            previous = (threads, rate);
+        }
+        let knee = steps
+            .windows(2)
+            .find(|pair| pair[1].added_per_thread < KNEE_EFFICIENCY)
+            .map(|pair| pair[0].threads);
+        Some(ScalingReport {
+            rate_unit: reports
+                .iter()
+                .find_map(|report| report.rate_unit.clone())
+                .unwrap_or_default(),
+            physical_cores,
+            steps,
+            knee,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A sweep over `logical_cores` with stage reports measuring `rates`, `None` for a
+    /// stage that failed.
+    fn measured(logical_cores: usize, rates: &[Option<f64>]) -> (TestPlan, Vec<StageReport>) {
+        let plan = sweep_plan("fibonacci", 10, logical_cores, None);
+        let reports = plan
+            .stages
+            .iter()
+            .zip(rates)
+            .map(|(stage, &rate)| {
+                let mut report = StageReport::skipped(stage);
+                report.outcome = if rate.is_some() { "passed" } else { "failed" }.to_string();
+                report.rate = rate;
+                report.rate_unit = Some("units/s".to_string());
+                report
+            })
+            .collect();
+        (plan, reports)
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "{} is not {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn sweep_doubles_up_to_every_cpu() {
+        assert_eq!(sweep_threads(1), vec![1]);
+        assert_eq!(sweep_threads(2), vec![1, 2]);
+        assert_eq!(sweep_threads(6), vec![1, 2, 4, 6]);
+        assert_eq!(sweep_threads(8), vec![1, 2, 4, 8]);
+        assert_eq!(sweep_threads(0), vec![1]);
+    }
+
+    #[test]
+    fn efficiency_is_relative_to_one_thread() {
+        let (plan, reports) = measured(8, &[Some(10.0), Some(20.0), Some(40.0), Some(50.0)]);
+        let report = ScalingReport::new(&plan, &reports, Some(4)).unwrap();
+        let threads: Vec<usize> = report.steps.iter().map(|s| s.threads).collect();
+        assert_eq!(threads, vec![1, 2, 4, 8]);
+        let last = &report.steps[3];
+        assert_close(last.speedup, 5.0);
+        assert_close(last.efficiency, 5.0 / 8.0);
+        // The last four threads added 10 units/s together, a quarter of one thread each
+        assert_close(last.added_per_thread, 0.25);
+        assert_eq!(report.rate_unit, "units/s");
+        assert_eq!(report.physical_cores, Some(4));
+    }
+
+    #[test]
+    fn knee_is_where_added_threads_fall_below_half() {
+        let (plan, reports) = measured(8, &[Some(10.0), Some(20.0), Some(40.0), Some(50.0)]);
+        let report = ScalingReport::new(&plan, &reports, None).unwrap();
+        assert_eq!(report.knee, Some(4));
+    }
+
+    #[test]
+    fn perfect_scaling_has_no_knee() {
+        let (plan, reports) = measured(8, &[Some(10.0), Some(20.0), Some(40.0), Some(80.0)]);
+        let report = ScalingReport::new(&plan, &reports, None).unwrap();
+        assert_eq!(report.knee, None);
+        assert!(
+            report
+                .steps
+                .iter()
+                .all(|s| (s.efficiency - 1.0).abs() < 1e-9)
+        );
+    }
+
+    #[test]
+    fn failed_stages_are_left_out() {
+        let (plan, reports) = measured(8, &[Some(10.0), None, Some(40.0), Some(80.0)]);
+        let report = ScalingReport::new(&plan, &reports, None).unwrap();
+        let threads: Vec<usize> = report.steps.iter().map(|s| s.threads).collect();
+        assert_eq!(threads, vec![1, 4, 8]);
+        assert_close(report.steps[1].added_per_thread, 1.0);
+    }
+
+    #[test]
+    fn needs_a_single_thread_baseline() {
+        let (plan, reports) = measured(4, &[None, Some(20.0), Some(40.0)]);
+        assert!(ScalingReport::new(&plan, &reports, None).is_none());
+        let (plan, reports) = measured(4, &[Some(0.0), Some(20.0), Some(40.0)]);
+        assert!(ScalingReport::new(&plan, &reports, None).is_none());
+    }
+}
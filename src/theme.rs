@@ -0,0 +1,50 @@
+use ratatui::style::Color;
+
+/// Colours of the interface that depend on the terminal's background. Chart series
+/// keep their own colours in every theme.
+#[derive(Clone, Copy, Debug)]
+pub struct Theme {
+    /// Focused input fields and screen titles.
+    pub accent: Color,
+    /// Selected options and buttons.
+    pub selected: Color,
+    /// Axis labels and popup text.
+    pub text: Color,
+    /// Side panels and popups.
+    pub background: Color,
+}
+
+impl Theme {
+    pub fn dark() -> Self {
+        Theme {
+            accent: Color::Cyan,
+            selected: Color::Green,
+            text: Color::White,
+            background: Color::Black,
+        }
+    }
+
+    pub fn light() -> Self {
+        Theme {
+            accent: Color::Blue,
+            selected: Color::Rgb(0, 128, 0),
+            text: Color::Black,
+            background: Color::White,
+        }
+    }
+
+    /// Parses a theme name, "dark" or "light".
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name.to_lowercase().as_str() {
+            "dark" => Ok(Theme::dark()),
+            "light" => Ok(Theme::light()),
+            _ => Err(format!("Unknown theme '{}', expected dark or light", name)),
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
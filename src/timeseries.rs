@@ -0,0 +1,354 @@
+/// Buckets kept per series before neighbouring buckets are merged. Bounds memory to
+/// a few tens of kilobytes per series however long a run lasts.
+const HISTORY_CAPACITY: usize = 2048;
+
+/// A run of consecutive samples reduced to their range.
+#[derive(Clone, Copy, Debug)]
+struct Bucket {
+    start: f64,
+    end: f64,
+    min: f64,
+    max: f64,
+    count: u32,
+}
+
+/// Every sample of a run in bounded memory. Samples are stored as they come until the
+/// capacity is reached, then pairs of buckets are merged and new buckets take twice as
+/// many samples, so the whole run stays available at a resolution that halves as it
+/// grows. Each bucket keeps its min and max, so short spikes survive downsampling.
+#[derive(Clone, Debug)]
+pub struct TimeSeries {
+    buckets: Vec<Bucket>,
+    /// Samples that go into one bucket at the current resolution.
+    samples_per_bucket: u32,
+    latest: Option<(f64, f64)>,
+}
+
+impl TimeSeries {
+    pub fn new() -> Self {
+        TimeSeries {
+            buckets: Vec::new(),
+            samples_per_bucket: 1,
+            latest: None,
+        }
+    }
+
+    /// Appends a sample. Times must not go backwards.
+    pub fn push(&mut self, t: f64, value: f64) {
+        self.latest = Some((t, value));
+        if let Some(last) = self.buckets.last_mut()
+            && last.count < self.samples_per_bucket
+        {
+            last.end = t;
+            last.min = last.min.min(value);
+            last.max = last.max.max(value);
+            last.count += 1;
+            return;
+        }
+        self.buckets.push(Bucket {
+            start: t,
+            end: t,
+            min: value,
+            max: value,
+            count: 1,
+        });
+        if self.buckets.len() > HISTORY_CAPACITY {
+            self.compact();
+        }
+    }
+
+    /// Merges neighbouring buckets, halving the resolution.
+    fn compact(&mut self) {
+        self.buckets = self
+            .buckets
+            .chunks(2)
+            .map(|pair| match pair {
+                [a, b] => Bucket {
+                    start: a.start,
+                    end: b.end,
+                    min: a.min.min(b.min),
+                    max: a.max.max(b.max),
+                    count: a.count + b.count,
+                },
+                [a] => *a,
+                _ => unreachable!(),
+            })
+            .collect();
+        self.samples_per_bucket *= 2;
+    }
+
+    pub fn clear(&mut self) {
+        *self = TimeSeries::new();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buckets.is_empty()
+    }
+
+    /// Highest value over the whole run.
+    pub fn max(&self) -> Option<f64> {
+        self.buckets.iter().map(|b| b.max).reduce(f64::max)
+    }
+
+    /// Time and value of the latest sample.
+    pub fn last(&self) -> Option<(f64, f64)> {
+        self.latest
+    }
+
+    /// (time, value) points within [start, end], at most about `max_points` of them.
+    /// Where samples have to be combined, both the min and the max are drawn.
+    pub fn points(&self, start: f64, end: f64, max_points: usize) -> Vec<(f64, f64)> {
+        let visible: Vec<Bucket> = self
+            .buckets
+            .iter()
+            .filter(|b| b.end >= start && b.start <= end)
+            .copied()
+            .collect();
+        let groups = (max_points / 2).max(1);
+        let per_group = visible.len().div_ceil(groups).max(1);
+
+        let mut points = Vec::with_capacity(visible.len().min(max_points) + 1);
+        for group in visible.chunks(per_group) {
+            let first = group[0];
+            let last = group[group.len() - 1];
+            let min = group.iter().map(|b| b.min).fold(f64::INFINITY, f64::min);
+            let max = group
+                .iter()
+                .map(|b| b.max)
+                .fold(f64::NEG_INFINITY, f64::max);
+            if min == max {
+                points.push((first.start, min));
+                if last.end > first.start {
+                    points.push((last.end, min));
+                }
+            } else {
+                // Keep the line's direction: falling buckets draw max before min
+                let rising = first.min + first.max <= last.min + last.max;
+                let (a, b) = if rising { (min, max) } else { (max, min) };
+                points.push((first.start, a));
+                points.push((last.end, b));
+            }
+        }
+        points
+    }
+}
+
+impl Default for TimeSeries {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The part of the run shown in Chart mode.
+pub struct Viewport {
+    /// 1.0 shows the whole run, 2.0 half of it and so on.
+    pub zoom: f64,
+    /// Left edge in seconds while panned, or `None` to follow the latest sample.
+    pub start: Option<f64>,
+}
+
+impl Viewport {
+    const MAX_ZOOM: f64 = 256.0;
+
+    pub fn new() -> Self {
+        Viewport {
+            zoom: 1.0,
+            start: None,
+        }
+    }
+
+    /// Visible [start, end] in seconds for a run of `total` seconds, `now` seconds in.
+    pub fn window(&self, total: f64, now: f64) -> (f64, f64) {
+        let total = total.max(1.0);
+        let width = total / self.zoom;
+        let start = match self.start {
+            Some(start) => start,
+            None => (now - width).max(0.0),
+        };
+        let start = start.clamp(0.0, total - width);
+        (start, start + width)
+    }
+
+    pub fn zoom_in(&mut self, total: f64, now: f64) {
+        self.zoom_to(self.zoom * 2.0, total, now);
+    }
+
+    pub fn zoom_out(&mut self, total: f64, now: f64) {
+        self.zoom_to(self.zoom / 2.0, total, now);
+    }
+
+    /// Zooms around the centre of the current window.
+    fn zoom_to(&mut self, zoom: f64, total: f64, now: f64) {
+        let (start, end) = self.window(total, now);
+        let centre = (start + end) / 2.0;
+        self.zoom = zoom.clamp(1.0, Self::MAX_ZOOM);
+        if self.start.is_some() {
+            self.start = Some(centre - total.max(1.0) / self.zoom / 2.0);
+        }
+    }
+
+    /// Moves the window by `fraction` of its width, negative for earlier. Panning past
+    /// the latest sample goes back to following it.
+    pub fn pan(&mut self, fraction: f64, total: f64, now: f64) {
+        let (start, end) = self.window(total, now);
+        let width = end - start;
+        let start = start + width * fraction;
+        self.start = if start + width >= now {
+            None
+        } else {
+            Some(start.max(0.0))
+        };
+    }
+
+    pub fn reset(&mut self) {
+        *self = Viewport::new();
+    }
+
+    pub fn is_live(&self) -> bool {
+        self.start.is_none()
+    }
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sampled(values: impl IntoIterator<Item = f64>) -> TimeSeries {
+        let mut series = TimeSeries::new();
+        for (t, value) in values.into_iter().enumerate() {
+            series.push(t as f64, value);
+        }
+        series
+    }
+
+    #[test]
+    fn keeps_every_sample_below_capacity() {
+        let series = sampled((0..10).map(f64::from));
+        let expected: Vec<(f64, f64)> = (0..10).map(|t| (t as f64, t as f64)).collect();
+        assert_eq!(series.points(0.0, 9.0, 100), expected);
+        assert_eq!(series.last(), Some((9.0, 9.0)));
+    }
+
+    #[test]
+    fn compacts_once_capacity_is_exceeded() {
+        let mut series = sampled((0..HISTORY_CAPACITY).map(|_| 1.0));
+        assert_eq!(series.buckets.len(), HISTORY_CAPACITY);
+        assert_eq!(series.samples_per_bucket, 1);
+        series.push(HISTORY_CAPACITY as f64, 1.0);
+        assert_eq!(series.buckets.len(), HISTORY_CAPACITY / 2 + 1);
+        assert_eq!(series.samples_per_bucket, 2);
+    }
+
+    #[test]
+    fn long_runs_stay_bounded_and_complete() {
+        let samples = 100_000;
+        let series = sampled((0..samples).map(|_| 1.0));
+        assert!(series.buckets.len() <= HISTORY_CAPACITY);
+        let counted: u32 = series.buckets.iter().map(|b| b.count).sum();
+        assert_eq!(counted, samples);
+        assert_eq!(series.buckets[0].start, 0.0);
+        assert_eq!(series.buckets.last().unwrap().end, (samples - 1) as f64);
+    }
+
+    #[test]
+    fn spikes_survive_compaction() {
+        let series = sampled((0..100_000).map(|t| if t == 5_000 { 100.0 } else { 0.0 }));
+        assert_eq!(series.max(), Some(100.0));
+        let points = series.points(0.0, 100_000.0, 200);
+        assert!(points.iter().any(|&(_, value)| value == 100.0));
+    }
+
+    #[test]
+    fn points_stay_within_the_window_and_budget() {
+        let series = sampled((0..100).map(f64::from));
+        let points = series.points(10.0, 20.0, 100);
+        assert_eq!(points.first(), Some(&(10.0, 10.0)));
+        assert_eq!(points.last(), Some(&(20.0, 20.0)));
+
+        let series = sampled((0..100_000).map(|t| (t % 7) as f64));
+        for max_points in [2, 10, 51, 400] {
+            assert!(series.points(0.0, 100_000.0, max_points).len() <= max_points);
+        }
+    }
+
+    #[test]
+    fn combined_points_keep_the_direction() {
+        let falling = sampled([10.0, 0.0]);
+        assert_eq!(falling.points(0.0, 1.0, 2), vec![(0.0, 10.0), (1.0, 0.0)]);
+        let rising = sampled([0.0, 10.0]);
+        assert_eq!(rising.points(0.0, 1.0, 2), vec![(0.0, 0.0), (1.0, 10.0)]);
+    }
+
+    #[test]
+    fn clear_empties_the_series() {
+        let mut series = sampled((0..5_000).map(f64::from));
+        series.clear();
+        assert!(series.is_empty());
+        assert_eq!(series.max(), None);
+        assert_eq!(series.last(), None);
+        assert_eq!(series.samples_per_bucket, 1);
+    }
+
+    #[test]
+    fn live_window_follows_the_latest_sample() {
+        let mut viewport = Viewport::new();
+        assert_eq!(viewport.window(100.0, 30.0), (0.0, 100.0));
+        viewport.zoom_in(100.0, 80.0);
+        assert_eq!(viewport.window(100.0, 80.0), (30.0, 80.0));
+        assert!(viewport.is_live());
+    }
+
+    #[test]
+    fn zoom_is_clamped() {
+        let mut viewport = Viewport::new();
+        viewport.zoom_out(100.0, 50.0);
+        assert_eq!(viewport.zoom, 1.0);
+        for _ in 0..20 {
+            viewport.zoom_in(100.0, 50.0);
+        }
+        assert_eq!(viewport.zoom, Viewport::MAX_ZOOM);
+    }
+
+    #[test]
+    fn zoom_keeps_the_centre_while_panned() {
+        let mut viewport = Viewport {
+            zoom: 4.0,
+            start: Some(50.0),
+        };
+        viewport.zoom_in(100.0, 100.0);
+        assert_eq!(viewport.window(100.0, 100.0), (56.25, 68.75));
+    }
+
+    #[test]
+    fn pan_is_clamped_to_the_run() {
+        let mut viewport = Viewport {
+            zoom: 4.0,
+            start: None,
+        };
+        assert_eq!(viewport.window(100.0, 100.0), (75.0, 100.0));
+        viewport.pan(-1.0, 100.0, 100.0);
+        assert_eq!(viewport.window(100.0, 100.0), (50.0, 75.0));
+        viewport.pan(-10.0, 100.0, 100.0);
+        assert_eq!(viewport.window(100.0, 100.0), (0.0, 25.0));
+        // Past the latest sample it follows it again
+        viewport.pan(10.0, 100.0, 100.0);
+        assert!(viewport.is_live());
+    }
+
+    #[test]
+    fn window_never_leaves_the_run() {
+        let viewport = Viewport {
+            zoom: 4.0,
+            start: Some(90.0),
+        };
+        assert_eq!(viewport.window(100.0, 100.0), (75.0, 100.0));
+        // Runs shorter than a second still get a usable window
+        assert_eq!(Viewport::new().window(0.0, 0.0), (0.0, 1.0));
+    }
+}